@@ -1,60 +1,253 @@
-#[macro_use]
-extern crate criterion;
-
-use criterion::Criterion;
-use criterion::{BatchSize, BenchmarkId};
-
-//TODO import your implementation here
-use conway::{apply_default_pattern, GameOfLife, GameOfLifeSolution, GameOfLiveBroken};
-
-const NUM_TICKS: u32 = 50;
-
-fn bench_tick(c: &mut Criterion) {
-    let mut group = c.benchmark_group("Tick");
-
-    for &(w, h) in [(8, 8), (64, 64)].iter() {
-        {
-            let mut game = GameOfLiveBroken::new(w, h);
-            apply_default_pattern(&mut game);
-            group.bench_function(
-                BenchmarkId::new("Broken", format!("{}x{}", w, h)),
-                move |b| {
-                    b.iter_batched(
-                        || game.clone(),
-                        |mut g| {
-                            for _ in 0..NUM_TICKS {
-                                g.tick()
-                            }
-                        },
-                        BatchSize::SmallInput,
-                    )
-                },
-            );
-        }
-
-        {
-            let mut game = GameOfLifeSolution::new(w, h);
-            apply_default_pattern(&mut game);
-            group.bench_function(
-                BenchmarkId::new("Solution", format!("{}x{}", w, h)),
-                move |b| {
-                    b.iter_batched(
-                        || game.clone(),
-                        |mut g| {
-                            for _ in 0..NUM_TICKS {
-                                g.tick()
-                            }
-                        },
-                        BatchSize::SmallInput,
-                    )
-                },
-            );
-        }
-
-        //TODO copy paste and modify one of the examples above to benchmark your implementation
-    }
-    group.finish();
-}
-
-criterion_group!(benches, bench_tick);
-criterion_main!(benches);
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use criterion::{BatchSize, BenchmarkId};
+
+//TODO import your implementation here
+#[cfg(feature = "simd")]
+use conway::GameOfLifeSimd;
+use conway::{
+    apply_default_pattern, GameOfLife, GameOfLifeBitPacked, GameOfLifeIncremental,
+    GameOfLifeParallel, GameOfLifeSolution, GameOfLifeTiled, GameOfLiveBroken, Pattern,
+};
+use std::path::Path;
+
+const NUM_TICKS: u32 = 50;
+
+fn bench_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Tick");
+
+    for &(w, h) in [(8, 8), (64, 64)].iter() {
+        {
+            let mut game = GameOfLiveBroken::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("Broken", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        {
+            let mut game = GameOfLifeSolution::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("Solution", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        {
+            let mut game = GameOfLifeParallel::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("Parallel", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        {
+            let mut game = GameOfLifeBitPacked::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("BitPacked", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        // only built with `cargo bench --features simd`
+        #[cfg(feature = "simd")]
+        {
+            let mut game = GameOfLifeSimd::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(BenchmarkId::new("Simd", format!("{}x{}", w, h)), move |b| {
+                b.iter_batched(
+                    || game.clone(),
+                    |mut g| {
+                        for _ in 0..NUM_TICKS {
+                            g.tick()
+                        }
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+
+        {
+            let mut game = GameOfLifeTiled::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("Tiled", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        {
+            let mut game = GameOfLifeIncremental::new(w, h);
+            apply_default_pattern(&mut game);
+            group.bench_function(
+                BenchmarkId::new("Incremental", format!("{}x{}", w, h)),
+                move |b| {
+                    b.iter_batched(
+                        || game.clone(),
+                        |mut g| {
+                            for _ in 0..NUM_TICKS {
+                                g.tick()
+                            }
+                        },
+                        BatchSize::SmallInput,
+                    )
+                },
+            );
+        }
+
+        //TODO copy paste and modify one of the examples above to benchmark your implementation
+    }
+    group.finish();
+}
+
+/// How the parallel implementation speeds up on a big board as it's given more threads, from 1 up
+/// to as many as the computer can run at once.
+fn bench_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Threads");
+    // big boards take a while to tick, so don't take as many samples
+    group.sample_size(10);
+
+    let (w, h) = (2000, 2000);
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2))
+        .take_while(|&n| n < max_threads)
+        .collect();
+    thread_counts.push(max_threads);
+
+    for threads in thread_counts {
+        let mut game = GameOfLifeParallel::new(w, h).with_threads(threads);
+        apply_default_pattern(&mut game);
+        group.bench_function(
+            BenchmarkId::new(format!("Parallel/{}x{}", w, h), threads),
+            move |b| {
+                b.iter_batched(
+                    || game.clone(),
+                    |mut g| {
+                        for _ in 0..NUM_TICKS {
+                            g.tick()
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/// How much the tiled and incremental implementations save by skipping the parts of the board that
+/// aren't changing, on a big board where only a small part of the pattern is active at a time.
+fn bench_sparse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Sparse");
+    group.sample_size(10);
+
+    let (w, h) = (600, 1000);
+    let pattern_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("patterns/blockerstacker.txt");
+    let pattern = Pattern::load(&pattern_file).unwrap();
+    let name = format!("blockerstacker/{}x{}", w, h);
+
+    let mut solution = GameOfLifeSolution::new(w, h);
+    pattern.apply(&mut solution);
+    let mut tiled = GameOfLifeTiled::new(w, h);
+    pattern.apply(&mut tiled);
+    let mut incremental = GameOfLifeIncremental::new(w, h);
+    pattern.apply(&mut incremental);
+    // the first tick has to work out the whole board, so get past it before measuring
+    solution.tick();
+    tiled.tick();
+    incremental.tick();
+
+    group.bench_function(BenchmarkId::new("Solution", &name), move |b| {
+        b.iter_batched(
+            || solution.clone(),
+            |mut g| {
+                for _ in 0..NUM_TICKS {
+                    g.tick()
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function(BenchmarkId::new("Tiled", &name), move |b| {
+        b.iter_batched(
+            || tiled.clone(),
+            |mut g| {
+                for _ in 0..NUM_TICKS {
+                    g.tick()
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function(BenchmarkId::new("Incremental", &name), move |b| {
+        b.iter_batched(
+            || incremental.clone(),
+            |mut g| {
+                for _ in 0..NUM_TICKS {
+                    g.tick()
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tick, bench_threads, bench_sparse);
+criterion_main!(benches);
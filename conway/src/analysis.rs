@@ -0,0 +1,920 @@
+use crate::game_of_life::GameOfLife;
+use crate::game_of_life_unbounded::GameOfLifeUnbounded;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// How a board repeats itself, once it's settled down: see `PeriodDetector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Periodicity {
+    /// How many generations it takes for the board to come back around to the same state.
+    pub period: u64,
+    /// The first generation of the cycle, i.e. when the board settled down.
+    pub start: u64,
+}
+
+impl Periodicity {
+    /// Whether the board has stopped changing altogether (i.e. it has period 1), e.g. because
+    /// it's all still lifes, or empty.
+    pub fn is_stable(&self) -> bool {
+        self.period == 1
+    }
+}
+
+impl fmt::Display for Periodicity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_stable() {
+            write!(f, "stable since generation {}", self.start)
+        } else {
+            write!(f, "period {} since generation {}", self.period, self.start)
+        }
+    }
+}
+
+/// Notices when a game becomes periodic, by remembering a hash of the board at every generation
+/// and waiting for one to come around again.
+///
+/// The first state to repeat must be where the cycle starts (if an earlier one were in the cycle,
+/// it would have repeated first), and the gap between the two is the period. Different boards can
+/// have the same hash, though, so the board is only taken to be periodic once it's been seen to be
+/// exactly the same again another period on, which means it's noticed a period later than it
+/// would be otherwise.
+#[derive(Clone, Debug, Default)]
+pub struct PeriodDetector {
+    repeats: Repeats,
+    last_generation: Option<u64>,
+    periodicity: Option<Periodicity>,
+}
+
+impl PeriodDetector {
+    pub fn new() -> PeriodDetector {
+        PeriodDetector::default()
+    }
+
+    /// Look at the board as it is at the given generation, and return how it repeats if it's now
+    /// been seen to.
+    ///
+    /// Boards have to be looked at one generation after another, so if the generation isn't the
+    /// one after the last one looked at (e.g. because the game jumped ahead), this starts over.
+    /// Boards with more than `MAX_OBSERVED_POPULATION` live cells aren't looked at, and start it
+    /// over too.
+    pub fn observe(&mut self, game: &dyn GameOfLife, generation: u64) -> Option<Periodicity> {
+        if game.population() > MAX_OBSERVED_POPULATION {
+            self.reset();
+            return None;
+        }
+        if self.last_generation.map(|last| last + 1) != Some(generation) {
+            self.reset();
+        }
+        self.last_generation = Some(generation);
+        if self.periodicity.is_none() {
+            self.periodicity = self.repeats.observe(&board_cells(game), generation);
+        }
+        self.periodicity
+    }
+
+    /// How the board repeats, if it's been seen to.
+    pub fn periodicity(&self) -> Option<Periodicity> {
+        self.periodicity
+    }
+
+    /// Forget everything seen so far, e.g. because cells have been toggled.
+    pub fn reset(&mut self) {
+        *self = PeriodDetector::default();
+    }
+}
+
+/// The most live cells a board can have for `PeriodDetector`, `SpaceshipDetector` and
+/// `StabilityDetector` to look at it. Looking at a board means listing, sorting and hashing every
+/// cell on it, which for the boards HashLife and unbounded games can grow to (or load straight
+/// from a Macrocell file) would take far longer than ticking them, and far more memory than
+/// there is. Bigger boards are passed over as though they'd been jumped past.
+pub const MAX_OBSERVED_POPULATION: usize = 100_000;
+
+/// The coordinates and states of every cell on the board that isn't dead, in order. Boards with
+/// dying cells (see `GameOfLife::cell_state`) have every cell checked, since dying cells aren't in
+/// `live_cells`, but otherwise just the live cells are looked at.
+fn board_cells(game: &dyn GameOfLife) -> Vec<(i64, i64, u8)> {
+    let mut cells: Vec<(i64, i64, u8)> = if game.num_states() == 2 {
+        game.live_cells().map(|(x, y)| (x, y, 1)).collect()
+    } else {
+        (0..game.height())
+            .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+            .filter_map(|(x, y)| match game.cell_state(x, y) {
+                Some(0) | None => None,
+                Some(state) => Some((x as i64, y as i64, state)),
+            })
+            .collect()
+    };
+    // the live cells can come in any order, but hashes of them mustn't depend on it
+    cells.sort_unstable();
+    cells
+}
+
+fn hash_cells(cells: &[(i64, i64, u8)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remembers the boards seen so far (or the cells on them, as given by `board_cells`), by their
+/// hashes, to notice when one comes around again.
+#[derive(Clone, Debug, Default)]
+struct Repeats {
+    // the generation each board was first seen at, by its hash
+    seen: HashMap<u64, u64>,
+    candidate: Option<Candidate>,
+}
+
+/// The last board whose hash had been seen before, which looks like it's the board repeating
+/// itself, but might just be a different board with the same hash.
+#[derive(Clone, Debug)]
+struct Candidate {
+    cells: Vec<(i64, i64, u8)>,
+    generation: u64,
+    periodicity: Periodicity,
+}
+
+impl Repeats {
+    /// Look at the board's cells as they are at the given generation (which must be the one after
+    /// the last one looked at), and return how the board repeats if it's now been seen to.
+    ///
+    /// A board whose hash has been seen before is kept as a candidate, and the board's only taken
+    /// to repeat once it's exactly the same as the candidate a period after it. From then on this
+    /// keeps returning how it repeats each period, as long as it carries on doing so.
+    fn observe(&mut self, cells: &[(i64, i64, u8)], generation: u64) -> Option<Periodicity> {
+        let start = *self.seen.entry(hash_cells(cells)).or_insert(generation);
+        match &mut self.candidate {
+            Some(candidate) if generation < candidate.generation + candidate.periodicity.period => {
+                None
+            }
+            Some(candidate) if candidate.cells == cells => {
+                candidate.generation = generation;
+                Some(candidate.periodicity)
+            }
+            _ => {
+                self.candidate = if start == generation {
+                    None
+                } else {
+                    Some(Candidate {
+                        cells: cells.to_vec(),
+                        generation,
+                        periodicity: Periodicity {
+                            period: generation - start,
+                            start,
+                        },
+                    })
+                };
+                None
+            }
+        }
+    }
+}
+
+/// Which way a spaceship flies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Straight up, down, left or right.
+    Orthogonal,
+    /// At 45 degrees, as far across as it goes down (or up).
+    Diagonal,
+    /// Any other angle, like a knight's move.
+    Oblique,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Direction::Orthogonal => "orthogonal",
+            Direction::Diagonal => "diagonal",
+            Direction::Oblique => "oblique",
+        })
+    }
+}
+
+/// A board that comes back to the same shape, but moved along: see `SpaceshipDetector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spaceship {
+    /// How many generations it takes for the board to come back to the same shape.
+    pub period: u64,
+    /// How far across the board moves each period.
+    pub dx: i64,
+    /// How far down the board moves each period.
+    pub dy: i64,
+    /// The first generation the board had the shape it keeps coming back to.
+    pub start: u64,
+}
+
+impl Spaceship {
+    pub fn direction(&self) -> Direction {
+        if self.dx == 0 || self.dy == 0 {
+            Direction::Orthogonal
+        } else if self.dx.abs() == self.dy.abs() {
+            Direction::Diagonal
+        } else {
+            Direction::Oblique
+        }
+    }
+
+    /// The spaceship's speed in the usual notation, where `c` is the speed of light (one cell
+    /// per generation): e.g. `c/4` for a glider, `2c/5` for a ship that moves two cells every
+    /// five generations, or `(2,1)c/6` for one that moves two cells one way and one the other.
+    pub fn speed(&self) -> String {
+        let (dx, dy) = (self.dx.abs(), self.dy.abs());
+        let distance = match self.direction() {
+            Direction::Oblique => format!("({},{})", dx.max(dy), dx.min(dy)),
+            _ if dx.max(dy) == 1 => String::new(),
+            _ => dx.max(dy).to_string(),
+        };
+        format!("{}c/{}", distance, self.period)
+    }
+}
+
+impl fmt::Display for Spaceship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {} {} spaceship, moving ({}, {}) every {} generations since generation {}",
+            self.speed(),
+            self.direction(),
+            self.dx,
+            self.dy,
+            self.period,
+            self.start
+        )
+    }
+}
+
+/// Notices when a game is a spaceship, by remembering a hash of the shape of the board (i.e. its
+/// cells relative to the top left of the smallest box around them) at every generation, and
+/// waiting for one to come around again somewhere else.
+///
+/// Only works when the whole board moves as one, so e.g. a spaceship next to an oscillator
+/// isn't spotted. On a bounded board, the spaceship has to be spotted before it crashes into
+/// the edge (or, on a torus, wraps around it).
+#[derive(Clone, Debug, Default)]
+pub struct SpaceshipDetector {
+    // the generation each shape was first seen at, and the top left of the box around it then,
+    // by its hash
+    seen: HashMap<u64, (u64, i64, i64)>,
+    last_generation: Option<u64>,
+    spaceship: Option<Spaceship>,
+}
+
+impl SpaceshipDetector {
+    pub fn new() -> SpaceshipDetector {
+        SpaceshipDetector::default()
+    }
+
+    /// Look at the board as it is at the given generation, and return the spaceship it is, if
+    /// it's now been seen to be one.
+    ///
+    /// As for `PeriodDetector::observe`, boards have to be looked at one generation after
+    /// another, so if the generation isn't the one after the last one looked at (or the board is
+    /// too big to look at), this starts over.
+    pub fn observe(&mut self, game: &dyn GameOfLife, generation: u64) -> Option<Spaceship> {
+        if game.population() > MAX_OBSERVED_POPULATION {
+            self.reset();
+            return None;
+        }
+        if self.last_generation.map(|last| last + 1) != Some(generation) {
+            self.reset();
+        }
+        self.last_generation = Some(generation);
+        if self.spaceship.is_some() {
+            return self.spaceship;
+        }
+        let mut cells = board_cells(game);
+        let left = cells.iter().map(|&(x, _, _)| x).min()?;
+        let top = cells.iter().map(|&(_, y, _)| y).min()?;
+        for (x, y, _) in &mut cells {
+            *x -= left;
+            *y -= top;
+        }
+        let (start, first_left, first_top) = *self
+            .seen
+            .entry(hash_cells(&cells))
+            .or_insert((generation, left, top));
+        // coming back to the same shape in the same place makes it an oscillator (or still
+        // life), not a spaceship
+        if (first_left, first_top) != (left, top) {
+            self.spaceship = Some(Spaceship {
+                period: generation - start,
+                dx: left - first_left,
+                dy: top - first_top,
+                start,
+            });
+        }
+        self.spaceship
+    }
+
+    /// The spaceship the board is, if it's been seen to be one.
+    pub fn spaceship(&self) -> Option<Spaceship> {
+        self.spaceship
+    }
+
+    /// Forget everything seen so far, e.g. because cells have been toggled.
+    pub fn reset(&mut self) {
+        *self = SpaceshipDetector::default();
+    }
+}
+
+/// How a board ended up once it settled down: see `StabilityDetector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stabilisation {
+    /// How what's left behind after the escaping gliders repeats itself, and since when.
+    pub periodicity: Periodicity,
+    /// How many cells were alive when the board was seen to have settled, counting the gliders.
+    pub population: usize,
+    /// How many gliders are flying away from everything else, never to come back.
+    pub escaping_gliders: usize,
+}
+
+impl fmt::Display for Stabilisation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, with population {} and {} escaping gliders",
+            self.periodicity, self.population, self.escaping_gliders
+        )
+    }
+}
+
+/// Notices when a game settles down, like `PeriodDetector`, but without waiting for the gliders
+/// it's thrown off to stop moving, which they never will on an unbounded board.
+///
+/// Each generation, gliders on their own (with no other live cells within two cells of them) are
+/// left out of the board's hash, and the board has settled once what's left repeats itself and
+/// every glider is past the edge of what's left and heading further away. Gliders are recognised
+/// by how they move under Conway's rule, so this only finds them in games running `B3/S23` (or
+/// rules with the same gliders), and never in games with dying cells.
+///
+/// On a bounded board, gliders are counted as escaping even though they'll hit the edge in the
+/// end, so an unbounded implementation gives the truest answer.
+#[derive(Clone, Debug, Default)]
+pub struct StabilityDetector {
+    // what was left after taking out the gliders each generation
+    repeats: Repeats,
+    last_generation: Option<u64>,
+    stabilisation: Option<Stabilisation>,
+}
+
+impl StabilityDetector {
+    pub fn new() -> StabilityDetector {
+        StabilityDetector::default()
+    }
+
+    /// Look at the board as it is at the given generation, and return how it ended up if it's
+    /// now been seen to settle down.
+    ///
+    /// As for `PeriodDetector::observe`, boards have to be looked at one generation after
+    /// another, so if the generation isn't the one after the last one looked at (or the board is
+    /// too big to look at), this starts over.
+    pub fn observe(&mut self, game: &dyn GameOfLife, generation: u64) -> Option<Stabilisation> {
+        if game.population() > MAX_OBSERVED_POPULATION {
+            self.reset();
+            return None;
+        }
+        if self.last_generation.map(|last| last + 1) != Some(generation) {
+            self.reset();
+        }
+        self.last_generation = Some(generation);
+        if self.stabilisation.is_some() {
+            return self.stabilisation;
+        }
+        let mut cells = board_cells(game);
+        let gliders = if game.num_states() == 2 {
+            find_gliders(&cells)
+        } else {
+            Vec::new()
+        };
+        let glider_cells: HashSet<(i64, i64)> = gliders
+            .iter()
+            .flat_map(|glider| glider.cells.iter().copied())
+            .collect();
+        cells.retain(|&(x, y, _)| !glider_cells.contains(&(x, y)));
+
+        // what's left might have repeated only because a glider is yet to come back and hit it,
+        // in which case it'll change again, and this will be seen to settle later on
+        if let Some(periodicity) = self.repeats.observe(&cells, generation) {
+            if gliders.iter().all(|glider| glider.is_escaping(&cells)) {
+                self.stabilisation = Some(Stabilisation {
+                    periodicity,
+                    population: game.population(),
+                    escaping_gliders: gliders.len(),
+                });
+            }
+        }
+        self.stabilisation
+    }
+
+    /// How the board ended up, if it's been seen to settle down.
+    pub fn stabilisation(&self) -> Option<Stabilisation> {
+        self.stabilisation
+    }
+
+    /// Forget everything seen so far, e.g. because cells have been toggled.
+    pub fn reset(&mut self) {
+        *self = StabilityDetector::default();
+    }
+}
+
+/// A glider found on the board, on its own.
+struct Glider {
+    cells: Vec<(i64, i64)>,
+    // which way it's flying, e.g. (1, -1) for up and to the right
+    dx: i64,
+    dy: i64,
+}
+
+impl Glider {
+    /// Whether the glider is past the edge of the box around the given cells (with room to spare,
+    /// so it can't touch them) and flying further away from it, in which case it'll never meet
+    /// them again if they stay where they are.
+    fn is_escaping(&self, others: &[(i64, i64, u8)]) -> bool {
+        const GAP: i64 = 2;
+        let (mut left, mut right, mut top, mut bottom) = (i64::MAX, i64::MIN, i64::MAX, i64::MIN);
+        for &(x, y, _) in others {
+            left = left.min(x);
+            right = right.max(x);
+            top = top.min(y);
+            bottom = bottom.max(y);
+        }
+        self.cells
+            .iter()
+            .all(|&(x, _)| self.dx > 0 && x > right + GAP)
+            || self
+                .cells
+                .iter()
+                .all(|&(x, _)| self.dx < 0 && x < left - GAP)
+            || self
+                .cells
+                .iter()
+                .all(|&(_, y)| self.dy > 0 && y > bottom + GAP)
+            || self
+                .cells
+                .iter()
+                .all(|&(_, y)| self.dy < 0 && y < top - GAP)
+    }
+}
+
+/// Find the gliders among the given cells that are on their own, i.e. with no other live cells
+/// within two cells of them.
+fn find_gliders(cells: &[(i64, i64, u8)]) -> Vec<Glider> {
+    let mut unvisited: HashSet<(i64, i64)> = cells.iter().map(|&(x, y, _)| (x, y)).collect();
+    let mut gliders = Vec::new();
+    for &(x, y, _) in cells {
+        if !unvisited.remove(&(x, y)) {
+            continue;
+        }
+        // gather up every cell close enough to this one to be part of the same thing; even once
+        // it's too big to be a glider, the rest of it has to be gathered up too, or else some
+        // glider shaped part of it could be taken for a glider on its own later on
+        let mut group = vec![(x, y)];
+        let mut next = 0;
+        while next < group.len() {
+            let (x, y) = group[next];
+            next += 1;
+            for ny in y - 2..=y + 2 {
+                for nx in x - 2..=x + 2 {
+                    if unvisited.remove(&(nx, ny)) {
+                        group.push((nx, ny));
+                    }
+                }
+            }
+        }
+        if group.len() == 5 {
+            if let Some(glider) = as_glider(group) {
+                gliders.push(glider);
+            }
+        }
+    }
+    gliders
+}
+
+/// Run the given five cells on their own to see if they're a glider, and which way it's flying.
+fn as_glider(cells: Vec<(i64, i64)>) -> Option<Glider> {
+    let mut game = GameOfLifeUnbounded::new(3, 3);
+    for &(x, y) in &cells {
+        game.set_cell_at(x, y, true);
+    }
+    let spaceship = find_spaceship(&mut game, 4)?;
+    if spaceship.period == 4 && spaceship.dx.abs() == 1 && spaceship.dy.abs() == 1 {
+        Some(Glider {
+            cells,
+            dx: spaceship.dx,
+            dy: spaceship.dy,
+        })
+    } else {
+        None
+    }
+}
+
+/// Tick the game until it settles down (apart from any escaping gliders), and return how it
+/// ended up, or `None` if it's still going after `max_generations` ticks.
+pub fn find_stabilisation(
+    game: &mut dyn GameOfLife,
+    max_generations: u64,
+) -> Option<Stabilisation> {
+    let mut detector = StabilityDetector::new();
+    for generation in 0..=max_generations {
+        if generation > 0 {
+            game.tick();
+        }
+        if let Some(stabilisation) = detector.observe(game, generation) {
+            return Some(stabilisation);
+        }
+    }
+    None
+}
+
+/// Tick the game until it's seen to be a spaceship, and return what kind, or `None` if it isn't
+/// one within `max_generations` ticks.
+pub fn find_spaceship(game: &mut dyn GameOfLife, max_generations: u64) -> Option<Spaceship> {
+    let mut detector = SpaceshipDetector::new();
+    for generation in 0..=max_generations {
+        if generation > 0 {
+            game.tick();
+        }
+        if let Some(spaceship) = detector.observe(game, generation) {
+            return Some(spaceship);
+        }
+    }
+    None
+}
+
+/// Tick the game until it becomes periodic, and return how it repeats, or `None` if it's still
+/// changing after `max_generations` ticks.
+pub fn find_period(game: &mut dyn GameOfLife, max_generations: u64) -> Option<Periodicity> {
+    let mut detector = PeriodDetector::new();
+    for generation in 0..=max_generations {
+        if generation > 0 {
+            game.tick();
+        }
+        if let Some(periodicity) = detector.observe(game, generation) {
+            return Some(periodicity);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod analysis_test {
+    use super::{
+        find_gliders, find_period, find_spaceship, find_stabilisation, hash_cells, Direction,
+        PeriodDetector, Periodicity, Repeats, Spaceship, SpaceshipDetector, Stabilisation,
+        StabilityDetector, MAX_OBSERVED_POPULATION,
+    };
+    use crate::format::PatternFormat;
+    use crate::game_of_life::GameOfLife;
+    use crate::game_of_life_generations::GameOfLifeGenerations;
+    use crate::game_of_life_hashlife::GameOfLifeHashLife;
+    use crate::game_of_life_solution::GameOfLifeSolution;
+    use crate::game_of_life_unbounded::GameOfLifeUnbounded;
+    use crate::pattern::Pattern;
+    use crate::placement::{Placement, Position};
+
+    #[test]
+    fn blocks_are_stable_straight_away() {
+        let mut game = GameOfLifeSolution::new(4, 4);
+        for &(x, y) in &[(1, 1), (2, 1), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        let periodicity = find_period(&mut game, 10).unwrap();
+        assert_eq!(
+            periodicity,
+            Periodicity {
+                period: 1,
+                start: 0
+            }
+        );
+        assert!(periodicity.is_stable());
+        assert_eq!(periodicity.to_string(), "stable since generation 0");
+    }
+
+    #[test]
+    fn finds_where_the_cycle_starts() {
+        // a T tetromino, which takes 9 generations to turn into a traffic light (four
+        // blinkers)
+        let mut game = GameOfLifeUnbounded::new(10, 10);
+        for &(x, y) in &[(0, 0), (1, 0), (2, 0), (1, 1)] {
+            game.toggle_cell(x, y);
+        }
+        let periodicity = find_period(&mut game, 20).unwrap();
+        assert_eq!(
+            periodicity,
+            Periodicity {
+                period: 2,
+                start: 9
+            }
+        );
+        assert_eq!(periodicity.to_string(), "period 2 since generation 9");
+    }
+
+    #[test]
+    fn boards_with_the_same_hash_have_to_be_the_same_to_repeat() {
+        let (first, second) = (vec![(0, 0, 1)], vec![(5, 5, 1)]);
+        let mut repeats = Repeats::default();
+        assert_eq!(repeats.observe(&first, 0), None);
+        // as though the two boards' hashes were the same, which makes the second look like the
+        // first come around again, but the one after it isn't the same as it
+        repeats.seen.insert(hash_cells(&second), 0);
+        assert_eq!(repeats.observe(&second, 1), None);
+        assert_eq!(repeats.observe(&first, 2), None);
+    }
+
+    #[test]
+    fn twin_bees_have_period_46() {
+        let pattern = Pattern::parse(
+            include_str!("../../patterns/twin-bees-oscillator.txt"),
+            PatternFormat::Rle,
+        )
+        .unwrap();
+        let mut game = GameOfLifeSolution::new(60, 40);
+        let placement = Placement {
+            position: Position::Centered,
+            ..Placement::default()
+        };
+        pattern.apply_with(&mut game, &placement);
+        assert_eq!(
+            find_period(&mut game, 200),
+            Some(Periodicity {
+                period: 46,
+                start: 0
+            })
+        );
+    }
+
+    #[test]
+    fn gliders_on_a_plane_are_not_periodic() {
+        let mut game = GameOfLifeUnbounded::new(10, 10);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        assert_eq!(find_period(&mut game, 100), None);
+    }
+
+    #[test]
+    fn dying_cells_are_part_of_the_state() {
+        // under Brian's Brain, a single live cell dies out over two generations
+        let mut game = GameOfLifeGenerations::new(5, 5).with_rule("B2/S/C3".parse().unwrap());
+        game.toggle_cell(2, 2);
+        assert_eq!(
+            find_period(&mut game, 10),
+            Some(Periodicity {
+                period: 1,
+                start: 2
+            })
+        );
+    }
+
+    fn load(contents: &str, game: &mut dyn GameOfLife) {
+        let pattern = Pattern::parse(contents, PatternFormat::Rle).unwrap();
+        let placement = Placement {
+            position: Position::Centered,
+            ..Placement::default()
+        };
+        pattern.apply_with(game, &placement);
+    }
+
+    #[test]
+    fn gliders_are_c4_diagonal() {
+        let mut game = GameOfLifeUnbounded::new(10, 10);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        let spaceship = find_spaceship(&mut game, 10).unwrap();
+        assert_eq!(
+            spaceship,
+            Spaceship {
+                period: 4,
+                dx: 1,
+                dy: 1,
+                start: 0
+            }
+        );
+        assert_eq!(spaceship.direction(), Direction::Diagonal);
+        assert_eq!(
+            spaceship.to_string(),
+            "a c/4 diagonal spaceship, moving (1, 1) every 4 generations since generation 0"
+        );
+    }
+
+    #[test]
+    fn lobsters_are_c7_diagonal_on_any_board() {
+        let lobster = include_str!("../../patterns/lobster-diagonal-spaceship.txt");
+        let games: Vec<Box<dyn GameOfLife>> = vec![
+            Box::new(GameOfLifeUnbounded::new(60, 60)),
+            Box::new(GameOfLifeSolution::new(60, 60)),
+        ];
+        for mut game in games {
+            load(lobster, game.as_mut());
+            let spaceship = find_spaceship(game.as_mut(), 50).unwrap();
+            assert_eq!((spaceship.period, spaceship.dx.abs()), (7, 1));
+            assert_eq!(spaceship.speed(), "c/7");
+            assert_eq!(spaceship.direction(), Direction::Diagonal);
+        }
+    }
+
+    #[test]
+    fn sir_robin_is_a_knightship() {
+        let mut game = GameOfLifeUnbounded::new(100, 100);
+        load(
+            include_str!("../../patterns/sir-robin-elementary-knightship.txt"),
+            &mut game,
+        );
+        let spaceship = find_spaceship(&mut game, 50).unwrap();
+        assert_eq!(spaceship.period, 6);
+        assert_eq!(spaceship.speed(), "(2,1)c/6");
+        assert_eq!(spaceship.direction(), Direction::Oblique);
+    }
+
+    #[test]
+    fn orthogonal_speeds() {
+        let ship = |dx, dy, period| Spaceship {
+            period,
+            dx,
+            dy,
+            start: 0,
+        };
+        assert_eq!(ship(0, -2, 4).speed(), "2c/4");
+        assert_eq!(ship(-1, 0, 3).speed(), "c/3");
+        assert_eq!(ship(1, 0, 3).direction(), Direction::Orthogonal);
+        assert_eq!(ship(-1, 3, 9).speed(), "(3,1)c/9");
+    }
+
+    #[test]
+    fn oscillators_are_not_spaceships() {
+        let mut game = GameOfLifeSolution::new(5, 5);
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        assert_eq!(find_spaceship(&mut game, 20), None);
+        assert_eq!(find_spaceship(&mut GameOfLifeSolution::new(5, 5), 20), None);
+    }
+
+    #[test]
+    fn r_pentominos_settle_at_1103_with_six_gliders() {
+        let mut game = GameOfLifeUnbounded::new(3, 3);
+        for &(x, y) in &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+            game.toggle_cell(x, y);
+        }
+        let stabilisation = find_stabilisation(&mut game, 2000).unwrap();
+        assert_eq!(
+            stabilisation,
+            Stabilisation {
+                periodicity: Periodicity {
+                    period: 2,
+                    start: 1103
+                },
+                population: 116,
+                escaping_gliders: 6
+            }
+        );
+    }
+
+    #[test]
+    fn switch_engines_settle_at_3911() {
+        let mut game = GameOfLifeHashLife::new(6, 4);
+        load(
+            include_str!("../../patterns/switch-engine-methuselah.txt"),
+            &mut game,
+        );
+        // jump most of the way there, since ticking one at a time takes a while; the detector only
+        // needs to see the end
+        game.tick_n(3800);
+        let mut detector = StabilityDetector::new();
+        let stabilisation = (3800..5000)
+            .find_map(|generation| {
+                if generation > 3800 {
+                    game.tick();
+                }
+                detector.observe(&game, generation)
+            })
+            .unwrap();
+        assert_eq!(
+            stabilisation,
+            Stabilisation {
+                periodicity: Periodicity {
+                    period: 2,
+                    start: 3911
+                },
+                population: 842,
+                escaping_gliders: 12
+            }
+        );
+    }
+
+    #[test]
+    fn gliders_heading_for_something_are_not_escaping() {
+        // a glider flying down and to the right, into a block; the block is stable and the
+        // glider's on its own, but they're going to collide
+        let mut game = GameOfLifeUnbounded::new(3, 3);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        for &(x, y) in &[(12, 12), (13, 12), (12, 13), (13, 13)] {
+            game.set_cell_at(x, y, true);
+        }
+        let stabilisation = find_stabilisation(&mut game, 200).unwrap();
+        assert_eq!(stabilisation.escaping_gliders, 0);
+        assert!(stabilisation.periodicity.start > 20);
+    }
+
+    #[test]
+    fn gliders_touching_debris_are_not_on_their_own() {
+        // a glider just past the end of a row of blocks, each within two cells of the next
+        let blocks = (0..3).flat_map(|block: i64| {
+            let x = block * 3;
+            vec![(x, 0), (x + 1, 0), (x, 1), (x + 1, 1)]
+        });
+        let glider = |dx: i64| {
+            [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+                .iter()
+                .map(move |&(x, y): &(i64, i64)| (x + dx, y))
+        };
+        let cells = |dx| {
+            let mut cells: Vec<(i64, i64, u8)> = blocks
+                .clone()
+                .chain(glider(dx))
+                .map(|(x, y)| (x, y, 1))
+                .collect();
+            cells.sort_unstable();
+            cells
+        };
+        assert_eq!(find_gliders(&cells(9)).len(), 0);
+        // but it is once it's moved away from them
+        assert_eq!(find_gliders(&cells(12)).len(), 1);
+    }
+
+    #[test]
+    fn stabilises_without_gliders() {
+        let mut game = GameOfLifeSolution::new(10, 10);
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        let stabilisation = find_stabilisation(&mut game, 10).unwrap();
+        assert_eq!(
+            stabilisation.to_string(),
+            "period 2 since generation 0, with population 3 and 0 escaping gliders"
+        );
+        assert_eq!(
+            find_stabilisation(&mut GameOfLifeSolution::new(5, 5), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn starts_over_after_a_jump() {
+        let mut game = GameOfLifeSolution::new(5, 5);
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        let mut detector = PeriodDetector::new();
+        assert_eq!(detector.observe(&game, 0), None);
+        // the blinker's back where it was, but generations 1 to 9 weren't seen, so that can't be
+        // taken to mean it has period 10
+        game.tick_n(10);
+        assert_eq!(detector.observe(&game, 10), None);
+        game.tick();
+        assert_eq!(detector.observe(&game, 11), None);
+        // it's back again at 12, but that's only taken for a repeat once it's exactly the same
+        // again another period on
+        for generation in 12..14 {
+            game.tick();
+            assert_eq!(detector.observe(&game, generation), None);
+        }
+        game.tick();
+        assert_eq!(
+            detector.observe(&game, 14),
+            Some(Periodicity {
+                period: 2,
+                start: 10
+            })
+        );
+    }
+
+    #[test]
+    fn boards_too_big_to_look_at_are_passed_over() {
+        // a block in every 8x8 square of a 4096 cell wide board, which are still lifes, but far
+        // too many of them to keep looking at
+        let mut mc = "[M2] (golly 2.0)\n$$$...**$...**$\n".to_owned();
+        for level in 4..=12 {
+            let child = level - 3;
+            mc += &format!("{} {} {} {} {}\n", level, child, child, child, child);
+        }
+        let mut game = GameOfLifeHashLife::new(10, 10);
+        game.load_macrocell(&mc, 0, 0).unwrap();
+        assert!(game.population() > MAX_OBSERVED_POPULATION);
+        let mut period_detector = PeriodDetector::new();
+        let mut spaceship_detector = SpaceshipDetector::new();
+        let mut stability_detector = StabilityDetector::new();
+        for generation in 0..3 {
+            assert_eq!(period_detector.observe(&game, generation), None);
+            assert_eq!(spaceship_detector.observe(&game, generation), None);
+            assert_eq!(stability_detector.observe(&game, generation), None);
+            game.tick();
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong when loading a pattern file.
+#[derive(Debug)]
+pub enum PatternError {
+    /// The pattern file couldn't be read at all.
+    Io(io::Error),
+    /// The pattern file was read, but its contents are malformed.
+    Parse(ParseError),
+    /// The pattern is too big for the board, or is placed so that part of it hangs off the edge.
+    DoesNotFit {
+        pattern_width: usize,
+        pattern_height: usize,
+        /// Where the top left of the pattern would have gone.
+        x: i64,
+        y: i64,
+        board_width: usize,
+        board_height: usize,
+    },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Io(e) => write!(f, "could not read pattern file: {}", e),
+            PatternError::Parse(e) => write!(f, "invalid pattern file: {}", e),
+            PatternError::DoesNotFit {
+                pattern_width,
+                pattern_height,
+                x,
+                y,
+                board_width,
+                board_height,
+            } => write!(
+                f,
+                "the {}x{} pattern doesn't fit on the {}x{} board with its top left at {}, {}",
+                pattern_width, pattern_height, board_width, board_height, x, y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PatternError::Io(e) => Some(e),
+            PatternError::Parse(e) => Some(e),
+            PatternError::DoesNotFit { .. } => None,
+        }
+    }
+}
+
+// these `From` impls are what let the `?` operator convert errors into a `PatternError` for us
+impl From<io::Error> for PatternError {
+    fn from(e: io::Error) -> PatternError {
+        PatternError::Io(e)
+    }
+}
+
+impl From<ParseError> for PatternError {
+    fn from(e: ParseError) -> PatternError {
+        PatternError::Parse(e)
+    }
+}
+
+/// A problem with the contents of a pattern file, along with where in the file it was found.
+///
+/// Both `line` and `column` start counting from 1, like most text editors do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError { line, column, kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The different ways that the contents of a pattern file can be malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// There was no `x = m, y = n` line before the pattern data started.
+    MissingHeader,
+    /// The header line was present but couldn't be understood; the string says why.
+    InvalidHeader(String),
+    /// A character that isn't allowed at this point in the file.
+    UnexpectedCharacter(char),
+    /// A cell in one of the dying states of a multi-state RLE file, which patterns can't hold
+    /// (only live and dead cells).
+    DyingCell(char),
+    /// A number (such as a coordinate) that couldn't be understood; the string says why.
+    InvalidNumber(String),
+    /// A quadtree node in a Macrocell file that is malformed or doesn't fit with the nodes it
+    /// refers to; the string says why.
+    InvalidNode(String),
+    /// A run count that was zero or too big to fit in memory.
+    InvalidRunCount,
+    /// A live cell that lies outside the width or height declared in the header.
+    CellOutOfBounds { x: usize, y: usize },
+    /// The file ended before the `!` that marks the end of the pattern data.
+    MissingTerminator,
+    /// The file is empty, and its extension isn't one that says what format it's in.
+    UnknownFormat,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::MissingHeader => write!(f, "missing `x = m, y = n` header line"),
+            ParseErrorKind::InvalidHeader(reason) => write!(f, "invalid header: {}", reason),
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            ParseErrorKind::DyingCell(c) => write!(
+                f,
+                "cells in dying states (like {:?}) can't be loaded, only live and dead ones",
+                c
+            ),
+            ParseErrorKind::InvalidNumber(reason) => write!(f, "invalid number: {}", reason),
+            ParseErrorKind::InvalidNode(reason) => write!(f, "invalid node: {}", reason),
+            ParseErrorKind::InvalidRunCount => write!(f, "run count is zero or too large"),
+            ParseErrorKind::CellOutOfBounds { x, y } => write!(
+                f,
+                "live cell at {}, {} lies outside the size given in the header",
+                x, y
+            ),
+            ParseErrorKind::MissingTerminator => {
+                write!(f, "pattern data ended without a terminating `!`")
+            }
+            ParseErrorKind::UnknownFormat => {
+                write!(
+                    f,
+                    "file is empty, and its extension doesn't say what format it's in"
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::lif;
+use crate::macrocell;
+use crate::pattern::Pattern;
+use crate::plaintext;
+use crate::rle;
+use std::path::Path;
+
+/// The pattern file formats that can be loaded and saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternFormat {
+    /// Run Length Encoded, usually `.rle` (and what all the files in `patterns/` use).
+    Rle,
+    /// Plaintext pictures of the pattern, usually `.cells`.
+    Plaintext,
+    /// Life 1.05 blocks of cells, usually `.lif`.
+    Life105,
+    /// Life 1.06 lists of coordinates, usually `.lif`.
+    Life106,
+    /// Golly's quadtree-based format for huge patterns, usually `.mc`.
+    Macrocell,
+}
+
+impl PatternFormat {
+    /// Work out which format a pattern file is in, from its contents or (if those are ambiguous)
+    /// its file extension. Falls back to RLE when neither gives a clear answer, unless the file is
+    /// empty, in which case there's nothing to go on at all and it's an error.
+    pub fn detect(pattern_filename: &Path, contents: &str) -> Result<PatternFormat, ParseError> {
+        let first_line = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+
+        // Life 1.0x and Macrocell files announce themselves, so trust that over whatever the file
+        // is called
+        Ok(if first_line.starts_with(macrocell::MACROCELL_HEADER) {
+            PatternFormat::Macrocell
+        } else if first_line.starts_with(lif::LIFE_105_HEADER) {
+            PatternFormat::Life105
+        } else if first_line.starts_with(lif::LIFE_106_HEADER) {
+            PatternFormat::Life106
+        } else if let Some(format) = PatternFormat::from_extension(pattern_filename) {
+            format
+        } else if first_line.is_empty() {
+            return Err(ParseError::new(1, 1, ParseErrorKind::UnknownFormat));
+        } else if first_line.starts_with('!')
+            || first_line.chars().all(|c| c == '.' || c == 'O' || c == '*')
+        {
+            PatternFormat::Plaintext
+        } else {
+            PatternFormat::Rle
+        })
+    }
+
+    /// Work out which format a pattern file is in from its file extension alone, if the extension
+    /// is one we know.
+    ///
+    /// Both Life 1.05 and 1.06 use `.lif`, so this treats those as Life 1.06 (the more common of
+    /// the two nowadays).
+    pub fn from_extension(pattern_filename: &Path) -> Option<PatternFormat> {
+        let extension = pattern_filename.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(PatternFormat::Rle),
+            "cells" => Some(PatternFormat::Plaintext),
+            "lif" | "life" => Some(PatternFormat::Life106),
+            "mc" => Some(PatternFormat::Macrocell),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse(self, contents: &str) -> Result<Pattern, ParseError> {
+        match self {
+            PatternFormat::Rle => rle::parse(contents),
+            PatternFormat::Plaintext => plaintext::parse(contents),
+            PatternFormat::Life105 => lif::parse_105(contents),
+            PatternFormat::Life106 => lif::parse_106(contents),
+            PatternFormat::Macrocell => macrocell::parse(contents),
+        }
+    }
+
+    pub(crate) fn write(self, pattern: &Pattern) -> String {
+        match self {
+            PatternFormat::Rle => rle::write(pattern),
+            PatternFormat::Plaintext => plaintext::write(pattern),
+            PatternFormat::Life105 => lif::write_105(pattern),
+            PatternFormat::Life106 => lif::write_106(pattern),
+            PatternFormat::Macrocell => macrocell::write(pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod format_test {
+    use super::PatternFormat;
+    use crate::error::{ParseError, ParseErrorKind};
+    use std::path::Path;
+
+    #[test]
+    fn detects_format_from_first_line() {
+        let unknown = Path::new("pattern.txt");
+        assert_eq!(
+            PatternFormat::detect(unknown, "#Life 1.06\n0 0\n"),
+            Ok(PatternFormat::Life106)
+        );
+        assert_eq!(
+            PatternFormat::detect(unknown, "#Life 1.05\n#P 0 0\n*\n"),
+            Ok(PatternFormat::Life105)
+        );
+        assert_eq!(
+            PatternFormat::detect(unknown, "!Name: Blinker\nOOO\n"),
+            Ok(PatternFormat::Plaintext)
+        );
+        assert_eq!(
+            PatternFormat::detect(unknown, ".O.\n.O.\n"),
+            Ok(PatternFormat::Plaintext)
+        );
+        assert_eq!(
+            PatternFormat::detect(unknown, "[M2] (golly 2.0)\n$$..*$...*$.***$\n4 0 0 0 1\n"),
+            Ok(PatternFormat::Macrocell)
+        );
+        assert_eq!(
+            PatternFormat::detect(unknown, "#C A glider\nx = 3, y = 3\nbo$2bo$3o!"),
+            Ok(PatternFormat::Rle)
+        );
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        assert_eq!(
+            PatternFormat::detect(Path::new("blinker.cells"), "OOO\n"),
+            Ok(PatternFormat::Plaintext)
+        );
+        assert_eq!(
+            PatternFormat::detect(Path::new("glider.RLE"), "x = 3, y = 3\nbo$2bo$3o!"),
+            Ok(PatternFormat::Rle)
+        );
+        assert_eq!(
+            PatternFormat::detect(Path::new("glider.lif"), "0 0\n1 0\n"),
+            Ok(PatternFormat::Life106)
+        );
+    }
+
+    #[test]
+    fn header_line_wins_over_extension() {
+        assert_eq!(
+            PatternFormat::detect(Path::new("glider.lif"), "#Life 1.05\n#P 0 0\n*\n"),
+            Ok(PatternFormat::Life105)
+        );
+    }
+
+    #[test]
+    fn empty_files_need_an_extension() {
+        assert_eq!(
+            PatternFormat::detect(Path::new("pattern.txt"), "\n  \n"),
+            Err(ParseError::new(1, 1, ParseErrorKind::UnknownFormat))
+        );
+        assert_eq!(
+            PatternFormat::detect(Path::new("empty.cells"), ""),
+            Ok(PatternFormat::Plaintext)
+        );
+    }
+}
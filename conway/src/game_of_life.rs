@@ -5,6 +5,21 @@ pub trait GameOfLife {
     /// and/or `y` are out of bounds.
     fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool>;
 
+    /// Return the state of the cell as a number: 0 if it's dead, 1 if it's alive, or (for games
+    /// with more than two states, such as those running Generations rules) 2 and up if it's
+    /// dying. Returns `None` if `x` and/or `y` are out of bounds.
+    ///
+    /// You don't need to implement this yourself; by default it's worked out from
+    /// `is_cell_alive`.
+    fn cell_state(&self, x: usize, y: usize) -> Option<u8> {
+        self.is_cell_alive(x, y).map(u8::from)
+    }
+
+    /// Return how many different states `cell_state` can return, e.g. 2 for dead and alive.
+    fn num_states(&self) -> u8 {
+        2
+    }
+
     /// Swap the given cell from alive to dead or dead to alive.
     ///
     /// If `x` or `y` is out of bounds, this method should do nothing.
@@ -13,13 +28,356 @@ pub trait GameOfLife {
     /// top-left-most cell should be toggled.
     fn toggle_cell(&mut self, x: usize, y: usize);
 
+    /// Bring the given cell to life if `alive` is true, or kill it if it's false, whatever state
+    /// it's in now. Dying cells (see `cell_state`) count as dead.
+    ///
+    /// If `x` or `y` is out of bounds, this method should do nothing.
+    ///
+    /// You don't need to implement this yourself; by default it toggles the cell if it isn't
+    /// already in the right state.
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        if self.is_cell_alive(x, y) == Some(!alive) {
+            self.toggle_cell(x, y);
+        }
+    }
+
+    /// Kill every live cell on the board.
+    ///
+    /// You don't need to implement this yourself; by default it kills each cell from
+    /// `live_cells`.
+    fn clear(&mut self) {
+        let live_cells: Vec<(i64, i64)> = self.live_cells().collect();
+        for (x, y) in live_cells {
+            self.set_cell_at(x, y, false);
+        }
+    }
+
+    /// Return how many cells are alive.
+    ///
+    /// You don't need to implement this yourself; by default it counts the cells from
+    /// `live_cells`.
+    fn population(&self) -> usize {
+        self.live_cells().count()
+    }
+
+    /// Return the coordinates of every live cell, in no particular order. They're signed, like
+    /// `cell_state_at`'s.
+    ///
+    /// You don't need to implement this yourself; by default it checks every cell on the board.
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        Box::new(
+            (0..self.height())
+                .flat_map(move |y| (0..self.width()).map(move |x| (x, y)))
+                .filter(move |&(x, y)| self.is_cell_alive(x, y) == Some(true))
+                .map(|(x, y)| (x as i64, y as i64)),
+        )
+    }
+
     /// Execute one timestep; i.e. cause cells to live, be born, or die based on the amount of
     /// neighbors they have.
     fn tick(&mut self);
 
+    /// Execute `n` timesteps, ending up just as if `tick` had been called `n` times.
+    ///
+    /// You don't need to implement this yourself; by default it calls `tick` `n` times. But
+    /// implementations that can get there faster (e.g. by jumping many generations at once, or
+    /// by stopping early once nothing is changing) can do that instead.
+    fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
     /// Return the current width in cells of the game.
     fn width(&self) -> usize;
 
     /// Return the current height in cells of the game.
     fn height(&self) -> usize;
+
+    /// Describe anything worth knowing about how the last tick was worked out (such as how much
+    /// of the board had to be looked at), for showing in the UI. Returns `None` if there's
+    /// nothing to say.
+    ///
+    /// You don't need to implement this yourself; by default there's nothing to say.
+    fn tick_report(&self) -> Option<String> {
+        None
+    }
+
+    /// Like `cell_state`, but with signed coordinates, so that games whose board goes on forever
+    /// (such as `GameOfLifeUnbounded`) can report cells above and to the left of the origin.
+    /// Returns `None` if `x` and/or `y` are out of bounds.
+    ///
+    /// You don't need to implement this yourself; by default it's worked out from `cell_state`.
+    fn cell_state_at(&self, x: i64, y: i64) -> Option<u8> {
+        if x < 0 || y < 0 {
+            None
+        } else {
+            self.cell_state(x as usize, y as usize)
+        }
+    }
+
+    /// Like `toggle_cell`, but with signed coordinates (see `cell_state_at`).
+    ///
+    /// You don't need to implement this yourself; by default it calls `toggle_cell`.
+    fn toggle_cell_at(&mut self, x: i64, y: i64) {
+        if x >= 0 && y >= 0 {
+            self.toggle_cell(x as usize, y as usize);
+        }
+    }
+
+    /// Like `set_cell`, but with signed coordinates (see `cell_state_at`).
+    ///
+    /// You don't need to implement this yourself; by default it calls `set_cell`.
+    fn set_cell_at(&mut self, x: i64, y: i64, alive: bool) {
+        if x >= 0 && y >= 0 {
+            self.set_cell(x as usize, y as usize, alive);
+        }
+    }
+
+    /// Return the smallest box containing every live cell, or `None` if there aren't any.
+    ///
+    /// You don't need to implement this yourself; by default it goes through `live_cells`.
+    fn live_bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::around(self.live_cells())
+    }
+}
+
+/// A boxed up game is a game too, so that e.g. `WithStats` can wrap a `Box<dyn GameOfLife>`. Every
+/// method goes through to the boxed game, including the ones it has its own versions of.
+impl<G: GameOfLife + ?Sized> GameOfLife for Box<G> {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        (**self).is_cell_alive(x, y)
+    }
+
+    fn cell_state(&self, x: usize, y: usize) -> Option<u8> {
+        (**self).cell_state(x, y)
+    }
+
+    fn num_states(&self) -> u8 {
+        (**self).num_states()
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        (**self).toggle_cell(x, y);
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        (**self).set_cell(x, y, alive);
+    }
+
+    fn clear(&mut self) {
+        (**self).clear();
+    }
+
+    fn population(&self) -> usize {
+        (**self).population()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        (**self).live_cells()
+    }
+
+    fn tick(&mut self) {
+        (**self).tick();
+    }
+
+    fn tick_n(&mut self, n: u64) {
+        (**self).tick_n(n);
+    }
+
+    fn width(&self) -> usize {
+        (**self).width()
+    }
+
+    fn height(&self) -> usize {
+        (**self).height()
+    }
+
+    fn tick_report(&self) -> Option<String> {
+        (**self).tick_report()
+    }
+
+    fn cell_state_at(&self, x: i64, y: i64) -> Option<u8> {
+        (**self).cell_state_at(x, y)
+    }
+
+    fn toggle_cell_at(&mut self, x: i64, y: i64) {
+        (**self).toggle_cell_at(x, y);
+    }
+
+    fn set_cell_at(&mut self, x: i64, y: i64, alive: bool) {
+        (**self).set_cell_at(x, y, alive);
+    }
+
+    fn live_bounding_box(&self) -> Option<BoundingBox> {
+        (**self).live_bounding_box()
+    }
+}
+
+/// A rectangle of cells, such as the smallest one containing every live cell on the board (see
+/// `GameOfLife::live_bounding_box`). The coordinates of its top left are signed, since it can be
+/// above or to the left of the origin in games whose board goes on forever.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BoundingBox {
+    /// The smallest box containing all of the given cells, or `None` if there aren't any.
+    pub(crate) fn around(cells: impl IntoIterator<Item = (i64, i64)>) -> Option<BoundingBox> {
+        let mut cells = cells.into_iter();
+        let (x, y) = cells.next()?;
+        let (min_x, min_y, max_x, max_y) = cells.fold((x, y, x, y), |(x0, y0, x1, y1), (x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        });
+        Some(BoundingBox {
+            x: min_x,
+            y: min_y,
+            width: (max_x - min_x + 1) as usize,
+            height: (max_y - min_y + 1) as usize,
+        })
+    }
+}
+
+#[cfg(test)]
+mod game_of_life_test {
+    use super::GameOfLife;
+    use crate::game_of_life_bit_packed::GameOfLifeBitPacked;
+    use crate::game_of_life_generations::GameOfLifeGenerations;
+    use crate::game_of_life_hashlife::GameOfLifeHashLife;
+    use crate::game_of_life_incremental::GameOfLifeIncremental;
+    use crate::game_of_life_parallel::GameOfLifeParallel;
+    use crate::game_of_life_solution::GameOfLifeSolution;
+    use crate::game_of_life_tiled::GameOfLifeTiled;
+    use crate::game_of_life_unbounded::GameOfLifeUnbounded;
+
+    // implementations that use the default methods, and ones that have their own
+    fn games() -> Vec<Box<dyn GameOfLife>> {
+        vec![
+            Box::new(GameOfLifeGenerations::new(70, 20)),
+            Box::new(GameOfLifeTiled::new(70, 20)),
+            Box::new(GameOfLifeIncremental::new(70, 20)),
+            Box::new(GameOfLifeSolution::new(70, 20)),
+            Box::new(GameOfLifeBitPacked::new(70, 20)),
+            Box::new(GameOfLifeParallel::new(70, 20).with_threads(2)),
+            Box::new(GameOfLifeUnbounded::new(70, 20)),
+            Box::new(GameOfLifeHashLife::new(70, 20)),
+        ]
+    }
+
+    fn sorted_live_cells(game: &dyn GameOfLife) -> Vec<(i64, i64)> {
+        let mut cells: Vec<(i64, i64)> = game.live_cells().collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    #[test]
+    fn set_cell_only_changes_cells_in_the_wrong_state() {
+        for mut game in games() {
+            game.set_cell(3, 4, true);
+            game.set_cell(3, 4, true);
+            game.set_cell(69, 19, true);
+            game.set_cell(5, 5, false);
+            // the far edge of the first word of a bit packed row
+            game.set_cell(62, 0, true);
+            game.set_cell(63, 0, true);
+            assert_eq!(
+                sorted_live_cells(&*game),
+                vec![(62, 0), (63, 0), (3, 4), (69, 19)]
+            );
+            assert_eq!(game.population(), 4);
+            game.set_cell(63, 0, false);
+            assert_eq!(game.is_cell_alive(63, 0), Some(false));
+            assert_eq!(game.population(), 3);
+        }
+    }
+
+    #[test]
+    fn live_cells_keep_up_with_ticks() {
+        for mut game in games() {
+            for x in 10..13 {
+                game.set_cell(x, 10, true);
+            }
+            game.tick();
+            assert_eq!(sorted_live_cells(&*game), vec![(11, 9), (11, 10), (11, 11)]);
+            assert_eq!(game.population(), 3);
+        }
+    }
+
+    #[test]
+    fn clear_kills_everything() {
+        for mut game in games() {
+            for &(x, y) in &[(0, 0), (1, 0), (40, 10), (69, 19)] {
+                game.set_cell(x, y, true);
+            }
+            game.clear();
+            assert_eq!(game.population(), 0);
+            assert_eq!(game.live_bounding_box(), None);
+            // the board still works afterwards
+            game.set_cell(2, 2, true);
+            assert_eq!(sorted_live_cells(&*game), vec![(2, 2)]);
+        }
+    }
+
+    #[test]
+    fn tick_n_is_the_same_as_ticking() {
+        for (mut jumped, mut ticked) in games().into_iter().zip(games()) {
+            // an R-pentomino, which keeps changing for over a thousand generations, next to a
+            // block, which never changes
+            for &(x, y) in &[
+                (31, 9),
+                (32, 9),
+                (30, 10),
+                (31, 10),
+                (31, 11),
+                (2, 2),
+                (3, 2),
+            ] {
+                jumped.set_cell(x, y, true);
+                ticked.set_cell(x, y, true);
+            }
+            // an odd number of generations, to check games that jump by powers of two
+            for &n in &[0, 1, 37] {
+                jumped.tick_n(n);
+                for _ in 0..n {
+                    ticked.tick();
+                }
+                assert_eq!(sorted_live_cells(&*jumped), sorted_live_cells(&*ticked));
+            }
+        }
+    }
+
+    #[test]
+    fn tick_n_copes_with_boards_that_stop_changing() {
+        for mut game in games() {
+            // a pre-block, which turns into a block and then sits still
+            for &(x, y) in &[(5, 5), (6, 5), (5, 6)] {
+                game.set_cell(x, y, true);
+            }
+            game.tick_n(1000);
+            assert_eq!(
+                sorted_live_cells(&*game),
+                vec![(5, 5), (6, 5), (5, 6), (6, 6)]
+            );
+        }
+    }
+
+    #[test]
+    fn unbounded_games_set_cells_anywhere() {
+        let games: Vec<Box<dyn GameOfLife>> = vec![
+            Box::new(GameOfLifeUnbounded::new(10, 10)),
+            Box::new(GameOfLifeHashLife::new(10, 10)),
+        ];
+        for mut game in games {
+            game.set_cell_at(-1000, 5, true);
+            game.set_cell_at(300, -7, true);
+            game.set_cell_at(300, -7, true);
+            assert_eq!(sorted_live_cells(&*game), vec![(300, -7), (-1000, 5)]);
+            game.set_cell_at(-1000, 5, false);
+            assert_eq!(game.population(), 1);
+        }
+    }
 }
@@ -0,0 +1,352 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use std::sync::Arc;
+
+/// A fast implementation of GameOfLife, which packs 64 cells into each `u64` and works out a
+/// whole word's worth of cells at once.
+///
+/// Rather than counting each cell's neighbours one at a time, it lines up the eight words of
+/// neighbours (shifting words along by a bit for the neighbours to the left and right) and adds
+/// them together with the same logic as an adder circuit, giving each cell's count as four bits
+/// spread over four words. Then it checks those counts against the rule, again 64 cells at a time.
+///
+/// Each row has an extra cell at each end, and there's an extra row above and below the board,
+/// holding whatever the topology says is past the edges (see `refresh_border`), so cells at the
+/// edges don't need handling specially.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeBitPacked {
+    width: usize,
+    height: usize,
+    // how many words each row (including its border cells) takes up
+    words_per_row: usize,
+    // the rows of the board, including the border, where cell `x`, `y` is bit `(x + 1) % 64` of
+    // word `(x + 1) / 64` of row `y + 1`
+    rows: Vec<u64>,
+    // for each word in a row, which of its bits are cells on the board rather than border cells
+    // or unused
+    on_board: Vec<u64>,
+    rule: Rule,
+    topology: Topology,
+}
+
+impl GameOfLifeBitPacked {
+    pub fn new(width: usize, height: usize) -> GameOfLifeBitPacked {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        let words_per_row = (width + 2).div_ceil(64);
+        let on_board = (0..words_per_row)
+            .map(|word| {
+                (0..64)
+                    .filter(|bit| (1..=width).contains(&(word * 64 + bit)))
+                    .fold(0, |mask, bit| mask | 1 << bit)
+            })
+            .collect();
+        GameOfLifeBitPacked {
+            width,
+            height,
+            words_per_row,
+            rows: vec![0; words_per_row * (height + 2)],
+            on_board,
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeBitPacked {
+        rule.assert_two_state_totalistic("GameOfLifeBitPacked");
+        GameOfLifeBitPacked { rule, ..self }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeBitPacked {
+        GameOfLifeBitPacked { topology, ..self }
+    }
+
+    /// The word and bit holding the cell at the given coordinates, which can be one cell into
+    /// the border.
+    fn position(&self, x: isize, y: isize) -> (usize, u64) {
+        let (column, row) = ((x + 1) as usize, (y + 1) as usize);
+        (row * self.words_per_row + column / 64, 1 << (column % 64))
+    }
+
+    /// Fill in the border around the board with the cells the topology says are there. On a plane
+    /// they're always dead, so there's nothing to do.
+    fn refresh_border(&mut self) {
+        if self.topology == Topology::Plane {
+            return;
+        }
+        let (width, height) = (self.width as isize, self.height as isize);
+        let border = (-1..=width)
+            .flat_map(|x| vec![(x, -1), (x, height)])
+            .chain((0..height).flat_map(|y| vec![(-1, y), (width, y)]));
+        for (x, y) in border {
+            let alive = match self.topology.wrap(x, y, self.width, self.height) {
+                Some((x, y)) => self.is_cell_alive(x, y) == Some(true),
+                None => false,
+            };
+            let (word, bit) = self.position(x, y);
+            if alive {
+                self.rows[word] |= bit;
+            } else {
+                self.rows[word] &= !bit;
+            }
+        }
+    }
+
+    /// How many words each row of the board (including its border cells) takes up.
+    pub(crate) fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    /// Move the board on a tick, with `next_rows` filling in the next state of the board's rows
+    /// (not including the border rows above and below it), e.g. by calling `next_rows`.
+    pub(crate) fn tick_with(&mut self, next_rows: impl FnOnce(&GameOfLifeBitPacked, &mut [u64])) {
+        self.refresh_border();
+        let words = self.words_per_row;
+        let mut next = vec![0; self.rows.len()];
+        next_rows(self, &mut next[words..(self.height + 1) * words]);
+        self.rows = next;
+    }
+
+    /// Like `tick_with`, but for a board shared between threads, which `next_rows` can hand out
+    /// more references to. If any of them are still around once it's done, the board is copied
+    /// before it's changed.
+    pub(crate) fn tick_shared(
+        board: &mut Arc<GameOfLifeBitPacked>,
+        next_rows: impl FnOnce(&Arc<GameOfLifeBitPacked>, &mut [u64]),
+    ) {
+        Arc::make_mut(board).refresh_border();
+        let words = board.words_per_row;
+        let mut next = vec![0; board.rows.len()];
+        next_rows(board, &mut next[words..(board.height + 1) * words]);
+        Arc::make_mut(board).rows = next;
+    }
+
+    /// Work out the next state of a band of rows, starting with `first_row` (where row 0 is the
+    /// border above the board) and going on for as many rows as fit in `next`. The rows either
+    /// side of the band are only read, so different bands can be worked out at the same time.
+    pub(crate) fn next_rows(&self, first_row: usize, next: &mut [u64]) {
+        let words = self.words_per_row;
+        let born: Vec<u32> = (0..=8).filter(|&n| self.rule.is_born(n)).collect();
+        let survives: Vec<u32> = (0..=8).filter(|&n| self.rule.survives(n)).collect();
+
+        for (row, next) in (first_row..).zip(next.chunks_mut(words)) {
+            let (above, this, below) = (
+                &self.rows[(row - 1) * words..row * words],
+                &self.rows[row * words..(row + 1) * words],
+                &self.rows[(row + 1) * words..(row + 2) * words],
+            );
+            for word in 0..words {
+                // each row's cells shifted one along, so that each bit lines up with the cell to
+                // its left or right, carrying bits across from the neighbouring words
+                let left = |cells: &[u64]| {
+                    let carry = if word > 0 { cells[word - 1] >> 63 } else { 0 };
+                    cells[word] << 1 | carry
+                };
+                let right = |cells: &[u64]| {
+                    let carry = if word + 1 < words {
+                        cells[word + 1] << 63
+                    } else {
+                        0
+                    };
+                    cells[word] >> 1 | carry
+                };
+
+                // add up the eight neighbours of every cell in the word
+                let (above_sum, above_carry) = full_add(left(above), above[word], right(above));
+                let (this_sum, this_carry) = half_add(left(this), right(this));
+                let (below_sum, below_carry) = full_add(left(below), below[word], right(below));
+                let (ones, ones_carry) = full_add(above_sum, this_sum, below_sum);
+                let (twos_partial, twos_carry) = full_add(above_carry, this_carry, below_carry);
+                let (twos, twos_partial_carry) = half_add(twos_partial, ones_carry);
+                let (fours, eights) = half_add(twos_carry, twos_partial_carry);
+                let count = [ones, twos, fours, eights];
+
+                let matching = |counts: &[u32]| {
+                    counts
+                        .iter()
+                        .fold(0, |matches, &n| matches | count_is(&count, n))
+                };
+                let alive = this[word];
+                next[word] =
+                    (!alive & matching(&born) | alive & matching(&survives)) & self.on_board[word];
+            }
+        }
+    }
+}
+
+/// Add up three words' worth of bits, giving the sum and carry bits for each position.
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let partial = a ^ b;
+    (partial ^ c, (a & b) | (partial & c))
+}
+
+/// Add up two words' worth of bits, giving the sum and carry bits for each position.
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+/// Which bits of a 4 bit count spread over four words (least significant first) equal `n`.
+fn count_is(count: &[u64; 4], n: u32) -> u64 {
+    count.iter().enumerate().fold(!0, |matches, (i, &bits)| {
+        matches & if n & 1 << i != 0 { bits } else { !bits }
+    })
+}
+
+impl GameOfLife for GameOfLifeBitPacked {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        if x < self.width && y < self.height {
+            let (word, bit) = self.position(x as isize, y as isize);
+            Some(self.rows[word] & bit != 0)
+        } else {
+            None
+        }
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            let (word, bit) = self.position(x as isize, y as isize);
+            self.rows[word] ^= bit;
+        }
+    }
+
+    fn tick(&mut self) {
+        self.tick_with(|board, next| board.next_rows(1, next));
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        if x < self.width && y < self.height {
+            let (word, bit) = self.position(x as isize, y as isize);
+            if alive {
+                self.rows[word] |= bit;
+            } else {
+                self.rows[word] &= !bit;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.rows.iter_mut().for_each(|word| *word = 0);
+    }
+
+    fn population(&self) -> usize {
+        let words = self.words_per_row;
+        self.rows[words..(self.height + 1) * words]
+            .chunks(words)
+            .flat_map(|row| row.iter().zip(&self.on_board))
+            .map(|(&word, &on_board)| (word & on_board).count_ones() as usize)
+            .sum()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        let words = self.words_per_row;
+        let rows = self.rows[words..(self.height + 1) * words].chunks(words);
+        Box::new(rows.enumerate().flat_map(move |(y, row)| {
+            row.iter().zip(&self.on_board).enumerate().flat_map(
+                move |(word, (&cells, &on_board))| {
+                    // pick the live cells out of the word one at a time, lowest bit first
+                    let mut cells = cells & on_board;
+                    std::iter::from_fn(move || {
+                        if cells == 0 {
+                            return None;
+                        }
+                        let bit = cells.trailing_zeros() as usize;
+                        cells &= cells - 1;
+                        Some(((word * 64 + bit - 1) as i64, y as i64))
+                    })
+                },
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod bit_packed_test {
+    use super::{GameOfLife, GameOfLifeBitPacked};
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, live_cells, scatter_cells};
+    use crate::topology::{Edges, Topology};
+
+    #[test]
+    fn blinker_oscillates() {
+        let mut game = GameOfLifeBitPacked::new(5, 5);
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(2, 1), (2, 2), (2, 3)]);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn two_neighbours_are_not_enough_for_a_birth() {
+        let mut game = GameOfLifeBitPacked::new(3, 3);
+        game.toggle_cell(0, 0);
+        game.toggle_cell(2, 0);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![]);
+    }
+
+    #[test]
+    fn follows_the_given_rule() {
+        // under Seeds (B2/S) both live cells die, but the column of cells between them is born
+        let mut game = GameOfLifeBitPacked::new(3, 3).with_rule("B2/S".parse().unwrap());
+        game.toggle_cell(0, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(1, 0), (1, 1), (1, 2)]);
+        // whereas under Conway's rule they'd just die
+        let mut game = GameOfLifeBitPacked::new(3, 3).with_rule(Rule::conway());
+        game.toggle_cell(0, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![]);
+    }
+
+    #[test]
+    fn gliders_wrap_around_a_torus() {
+        let mut game = GameOfLifeBitPacked::new(6, 6).with_topology(Topology::Torus);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        let start = live_cells(&game);
+        for _ in 0..4 * 6 {
+            game.tick();
+        }
+        assert_eq!(live_cells(&game), start);
+    }
+
+    #[test]
+    fn runs_like_the_solution_across_word_boundaries() {
+        // wide enough that rows take up three words, with cells right by the edges of words
+        let (width, height) = (140, 12);
+        let topologies = [
+            Topology::Plane,
+            Topology::Torus,
+            Topology::KleinBottle {
+                twisted: Edges::Vertical,
+            },
+        ];
+        for &rule in &["B3/S23", "B36/S23", "B0/S8", "B2/S"] {
+            for &topology in &topologies {
+                let rule: Rule = rule.parse().unwrap();
+                let mut packed = GameOfLifeBitPacked::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology);
+                scatter_cells(&mut packed, 12345);
+                assert_runs_like_the_solution(&mut packed, rule, topology, 10, None, "bit packed");
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// An implementation of GameOfLife that supports "Generations" rules such as Brian's Brain
+/// (`B2/S/C3`), where cells that die go through a number of dying states before they're dead.
+///
+/// It runs ordinary two-state rules too, but the solution is a fair bit simpler for those.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeGenerations {
+    width: usize,
+    height: usize,
+    // 0 for dead, 1 for alive, and 2 and up for dying; see `GameOfLife::cell_state`
+    cells: Vec<u8>,
+    rule: Rule,
+    topology: Topology,
+}
+
+impl GameOfLifeGenerations {
+    pub fn new(width: usize, height: usize) -> GameOfLifeGenerations {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        GameOfLifeGenerations {
+            width,
+            height,
+            cells: vec![0; width * height],
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeGenerations {
+        rule.assert_totalistic("GameOfLifeGenerations");
+        GameOfLifeGenerations { rule, ..self }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeGenerations {
+        GameOfLifeGenerations { topology, ..self }
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    /// Count the live neighbours of the given cell; dying cells don't count.
+    fn live_neighbours(&self, x: usize, y: usize) -> u32 {
+        let mut count = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let neighbour =
+                    self.topology
+                        .wrap(x as isize + dx, y as isize + dy, self.width, self.height);
+                if let Some((nx, ny)) = neighbour {
+                    if (dx, dy) != (0, 0) && self.cells[nx + ny * self.width] == 1 {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+}
+
+impl GameOfLife for GameOfLifeGenerations {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.cell_state(x, y).map(|state| state == 1)
+    }
+
+    fn cell_state(&self, x: usize, y: usize) -> Option<u8> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    fn num_states(&self) -> u8 {
+        self.rule.states()
+    }
+
+    /// Kill live cells, and bring dead or dying cells to life.
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = if self.cells[i] == 1 { 0 } else { 1 };
+        }
+    }
+
+    fn tick(&mut self) {
+        let mut next = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let state = self.cells[x + y * self.width];
+                next.push(
+                    self.rule
+                        .next_generations_state(state, self.live_neighbours(x, y)),
+                );
+            }
+        }
+        self.cells = next;
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod generations_test {
+    use super::{GameOfLife, GameOfLifeGenerations};
+    use crate::rule::Rule;
+
+    fn states(game: &GameOfLifeGenerations) -> Vec<Vec<u8>> {
+        (0..game.height())
+            .map(|y| {
+                (0..game.width())
+                    .map(|x| game.cell_state(x, y).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn brians_brain_cells_fire_then_rest() {
+        let mut game = GameOfLifeGenerations::new(4, 3).with_rule("B2/S/C3".parse().unwrap());
+        assert_eq!(game.num_states(), 3);
+        game.toggle_cell(1, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        // the pair fires into the cells above and below, and starts dying itself
+        assert_eq!(
+            states(&game),
+            vec![vec![0, 1, 1, 0], vec![0, 2, 2, 0], vec![0, 1, 1, 0]]
+        );
+        game.tick();
+        // the dying pair is dead now, and isn't reborn despite having neighbours firing
+        assert_eq!(game.cell_state(1, 1), Some(0));
+        assert_eq!(game.is_cell_alive(1, 0), Some(false));
+        assert_eq!(game.cell_state(1, 0), Some(2));
+    }
+
+    #[test]
+    fn runs_two_state_rules_like_life() {
+        let mut game = GameOfLifeGenerations::new(5, 5).with_rule(Rule::conway());
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        game.tick();
+        assert_eq!(game.is_cell_alive(2, 1), Some(true));
+        assert_eq!(game.is_cell_alive(1, 2), Some(false));
+        assert_eq!(game.cell_state(1, 2), Some(0));
+    }
+}
@@ -0,0 +1,871 @@
+use crate::error::ParseError;
+use crate::game_of_life::{BoundingBox, GameOfLife};
+use crate::macrocell::MacrocellTree;
+use crate::rule::{Rule, RuleTable};
+use std::collections::HashMap;
+
+/// An index into `GameOfLifeHashLife::nodes`.
+type NodeId = u32;
+
+/// A square of cells `2^level` wide, split into four quadrants. Level 0 nodes are single cells.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// The north west, north east, south west and south east quadrants, in that order (unused
+    /// for single cells).
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+}
+
+/// An implementation of GameOfLife using Bill Gosper's HashLife algorithm, which can run patterns
+/// for millions (or far more) generations, on a board that goes on forever in every direction.
+///
+/// The board is stored as a quadtree, where identical squares of cells anywhere on the board (or
+/// at any point in time) are the same node, so repetitive patterns take up very little space.
+/// What each node's middle looks like some number of generations later is remembered, so once
+/// it's been worked out it never has to be worked out again. Together, those let `step_pow2` jump
+/// `2^k` generations at a time, by piecing the answer together from smaller jumps of smaller
+/// nodes.
+///
+/// Remembered nodes and results are thrown away by a garbage collector once there are more than
+/// a given number of nodes (see `with_max_nodes`), so long runs don't use up all the memory.
+///
+/// As for `GameOfLifeUnbounded`, the width and height it's created with are just the size of the
+/// area that patterns are put in and that the UI starts out showing.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeHashLife {
+    width: usize,
+    height: usize,
+    table: RuleTable,
+    // every node, where each distinct node appears exactly once (so that they can be compared
+    // just by their ids); ids 0 and 1 are the dead and live cells
+    nodes: Vec<Node>,
+    // the id of the node with each set of children, to keep nodes distinct
+    ids: HashMap<[NodeId; 4], NodeId>,
+    // the middle half of a node `2^k` generations later, keyed by the node and `k`
+    results: HashMap<(NodeId, u8), NodeId>,
+    // the empty node of each level
+    empty: Vec<NodeId>,
+    // the whole board, centred on the origin, so the root covers `-2^(level-1)..2^(level-1)` in
+    // both directions
+    root: NodeId,
+    max_nodes: usize,
+}
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+impl GameOfLifeHashLife {
+    /// How many nodes there can be before the garbage collector runs, by default.
+    pub const DEFAULT_MAX_NODES: usize = 1 << 21;
+
+    // the smallest level the root can be at
+    const MIN_ROOT_LEVEL: u8 = 3;
+    // the largest level the root can be at, so that coordinates fit in an `i64`
+    const MAX_ROOT_LEVEL: u8 = 62;
+
+    pub fn new(width: usize, height: usize) -> GameOfLifeHashLife {
+        let cell = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        let mut game = GameOfLifeHashLife {
+            width,
+            height,
+            table: Rule::conway().lookup_table(),
+            nodes: vec![cell(0), cell(1)],
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            max_nodes: GameOfLifeHashLife::DEFAULT_MAX_NODES,
+        };
+        game.root = game.empty_node(GameOfLifeHashLife::MIN_ROOT_LEVEL);
+        game
+    }
+
+    /// Run the game by a two state rule without births on 0, instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeHashLife {
+        rule.assert_two_state("GameOfLifeHashLife");
+        assert!(
+            !rule.is_born(0),
+            "{} has births on 0, which would fill the whole board",
+            rule
+        );
+        GameOfLifeHashLife {
+            table: rule.lookup_table(),
+            // results worked out by the old rule are no good any more
+            results: HashMap::new(),
+            ..self
+        }
+    }
+
+    /// Run the garbage collector whenever there are more than the given number of nodes, rather
+    /// than `DEFAULT_MAX_NODES`. Each node takes up around 50 bytes, plus its share of the
+    /// remembered results. The collector only runs between steps, and keeps every node the board
+    /// is made of, so the limit needs to be comfortably bigger than the board needs. Nothing is
+    /// thrown away in the middle of a step, so a single big `step_pow2` of a chaotic pattern can
+    /// go well past the limit before the collector gets a chance to run.
+    pub fn with_max_nodes(self, max_nodes: usize) -> GameOfLifeHashLife {
+        GameOfLifeHashLife { max_nodes, ..self }
+    }
+
+    /// Replace the board with the pattern in the given Macrocell file, with the top left of its
+    /// live cells at `x`, `y` (as for a `Pattern` put there with `Placement`). The board's
+    /// quadtree is built straight from the file's, without listing every live cell, so patterns
+    /// far too big for any other implementation can be loaded (as long as they're repetitive
+    /// enough to fit in a Macrocell file in the first place).
+    ///
+    /// The file's rule isn't used; see `with_rule` for that.
+    pub fn load_macrocell(&mut self, contents: &str, x: i64, y: i64) -> Result<(), ParseError> {
+        let tree = MacrocellTree::parse(contents)?;
+        let root = tree.fold(
+            self,
+            GameOfLifeHashLife::leaf,
+            |game, _, children| game.join(children),
+            |game, level| game.empty_node(level as u8),
+        );
+        // the file's tree is centred on the origin, as Golly has it
+        self.root = match root {
+            Some(root) => root,
+            None => self.empty_node(GameOfLifeHashLife::MIN_ROOT_LEVEL),
+        };
+        if let Some(bounding_box) = self.live_bounding_box() {
+            self.shift(x - bounding_box.x, y - bounding_box.y);
+        }
+        Ok(())
+    }
+
+    /// How many nodes are being kept around at the moment.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Move the game on by `2^k` generations in one go; `step_pow2(0)` is the same as `tick()`.
+    ///
+    /// This is far quicker than ticking `2^k` times for all but the most chaotic patterns, and
+    /// quicker still the more often the same `k` is used.
+    ///
+    /// Panics if the pattern would grow (or fly) further than `2^61` cells from the origin, past
+    /// which coordinates don't fit in an `i64`.
+    pub fn step_pow2(&mut self, k: u8) {
+        assert!(
+            k <= GameOfLifeHashLife::MAX_ROOT_LEVEL - 3,
+            "can't step by 2^{} generations at once",
+            k
+        );
+        if self.nodes.len() > self.max_nodes {
+            self.collect_garbage();
+        }
+        // Stepping a node works out what its middle half will be; for that to contain the
+        // whole pattern afterwards, the pattern has to start out in the middle quarter, with a
+        // big enough border of empty space around that for it to grow into.
+        while self.node(self.root).level < k + 2 || !self.is_in_middle_quarter(self.root) {
+            assert!(
+                self.node(self.root).level < GameOfLifeHashLife::MAX_ROOT_LEVEL,
+                "the pattern has grown too far from the origin to step any further"
+            );
+            self.root = self.expand(self.root);
+        }
+        let expanded = self.expand(self.root);
+        self.root = self.advance(expanded, k);
+    }
+
+    /// Throw away every node that isn't part of the current board, along with every remembered
+    /// result. This happens automatically whenever there are too many nodes (see
+    /// `with_max_nodes`), but can be done by hand too, e.g. before leaving the game alone.
+    pub fn collect_garbage(&mut self) {
+        // copy across the nodes still in use, making sure children are copied before parents so
+        // their new ids are known
+        let mut new_ids: HashMap<NodeId, NodeId> = HashMap::new();
+        new_ids.insert(DEAD, DEAD);
+        new_ids.insert(ALIVE, ALIVE);
+        let mut nodes = self.nodes[..2].to_vec();
+        let mut ids = HashMap::new();
+        let mut pending = vec![(self.root, false)];
+        while let Some((id, children_done)) = pending.pop() {
+            if new_ids.contains_key(&id) {
+                continue;
+            }
+            let node = self.node(id);
+            if children_done {
+                let children = node.children.map(|child| new_ids[&child]);
+                let new_id = nodes.len() as NodeId;
+                nodes.push(Node { children, ..node });
+                ids.insert(children, new_id);
+                new_ids.insert(id, new_id);
+            } else {
+                pending.push((id, true));
+                pending.extend(node.children.iter().map(|&child| (child, false)));
+            }
+        }
+        self.root = new_ids[&self.root];
+        self.nodes = nodes;
+        self.ids = ids;
+        self.results.clear();
+        self.empty.truncate(1);
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    /// Find (or make) the node with the given quadrants.
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.node(children[0]).level + 1,
+            population: children
+                .iter()
+                .map(|&child| self.node(child).population)
+                .fold(0, u64::saturating_add),
+        });
+        self.ids.insert(children, id);
+        id
+    }
+
+    /// Make the 8x8 node for a Macrocell leaf, whose rows have the leftmost cell in the lowest bit.
+    fn leaf(&mut self, rows: &[u8; 8]) -> NodeId {
+        // build it up from the cells, a level at a time
+        let mut nodes: Vec<Vec<NodeId>> = rows
+            .iter()
+            .map(|row| {
+                (0..8)
+                    .map(|x| if row & (1 << x) != 0 { ALIVE } else { DEAD })
+                    .collect()
+            })
+            .collect();
+        while nodes.len() > 1 {
+            let size = nodes.len() / 2;
+            nodes = (0..size)
+                .map(|y| {
+                    (0..size)
+                        .map(|x| {
+                            self.join([
+                                nodes[2 * y][2 * x],
+                                nodes[2 * y][2 * x + 1],
+                                nodes[2 * y + 1][2 * x],
+                                nodes[2 * y + 1][2 * x + 1],
+                            ])
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+        nodes[0][0]
+    }
+
+    fn empty_node(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let smaller = *self.empty.last().unwrap();
+            let bigger = self.join([smaller; 4]);
+            self.empty.push(bigger);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Make a node twice the size of the given one, with the given one in its middle.
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let node = self.node(id);
+        let empty = self.empty_node(node.level - 1);
+        let [nw, ne, sw, se] = node.children;
+        let children = [
+            self.join([empty, empty, empty, nw]),
+            self.join([empty, empty, ne, empty]),
+            self.join([empty, sw, empty, empty]),
+            self.join([se, empty, empty, empty]),
+        ];
+        self.join(children)
+    }
+
+    /// Move every live cell on the board `dx` cells to the right and `dy` cells down, building the
+    /// moved board out of the nodes that are already there rather than going through the cells.
+    fn shift(&mut self, dx: i64, dy: i64) {
+        let bounding_box = match self.live_bounding_box() {
+            Some(bounding_box) => bounding_box,
+            None => return,
+        };
+        let (left, top) = (bounding_box.x + dx, bounding_box.y + dy);
+        let (right, bottom) = (
+            left + bounding_box.width as i64,
+            top + bounding_box.height as i64,
+        );
+        // make sure the root reaches far enough to hold the pattern where it's going
+        while {
+            let radius = self.root_radius();
+            left < -radius || top < -radius || right > radius || bottom > radius
+        } {
+            assert!(
+                self.node(self.root).level < GameOfLifeHashLife::MAX_ROOT_LEVEL,
+                "can't move the pattern that far from the origin"
+            );
+            self.root = self.expand(self.root);
+        }
+        // the old root is one square in a grid of squares the same size, the rest of which are
+        // empty, and the new root straddles up to four of them
+        let (level, radius) = (self.node(self.root).level, self.root_radius());
+        let size = 2 * radius;
+        let (grid_x, grid_y) = ((-dx).div_euclid(size), (-dy).div_euclid(size));
+        let empty = self.empty_node(level);
+        let square = |x, y| if (x, y) == (0, 0) { self.root } else { empty };
+        let window = [
+            square(grid_x, grid_y),
+            square(grid_x + 1, grid_y),
+            square(grid_x, grid_y + 1),
+            square(grid_x + 1, grid_y + 1),
+        ];
+        self.root = self.window(
+            window,
+            level,
+            ((-dx).rem_euclid(size), (-dy).rem_euclid(size)),
+            &mut HashMap::new(),
+        );
+    }
+
+    /// Make the node at the given level found at `x`, `y` (each less than the node's width) from
+    /// the top left of the square made of the given four nodes of that level.
+    ///
+    /// When a whole board is being moved, every node of a given level is in the same place
+    /// relative to the nodes it overlaps, so remembering results in `memo` by the four nodes
+    /// means each different neighbourhood of nodes is only looked at once.
+    fn window(
+        &mut self,
+        window: [NodeId; 4],
+        level: u8,
+        (x, y): (i64, i64),
+        memo: &mut HashMap<([NodeId; 4], u8), NodeId>,
+    ) -> NodeId {
+        if (x, y) == (0, 0) {
+            return window[0];
+        }
+        if window.iter().all(|&id| self.node(id).population == 0) {
+            return self.empty_node(level);
+        }
+        if let Some(&node) = memo.get(&(window, level)) {
+            return node;
+        }
+        // the square is a 4x4 grid of the nodes' quadrants, and each quadrant of the node being
+        // made is found in a window of that grid, in the same way
+        let half = 1 << (level - 1);
+        let quadrants = window.map(|id| self.node(id).children);
+        let grid =
+            |x: i64, y: i64| quadrants[(y / 2 * 2 + x / 2) as usize][(y % 2 * 2 + x % 2) as usize];
+        let mut children = [DEAD; 4];
+        for (quadrant, child) in children.iter_mut().enumerate() {
+            let (x, y) = (
+                x + (quadrant % 2) as i64 * half,
+                y + (quadrant / 2) as i64 * half,
+            );
+            let (column, row) = (x / half, y / half);
+            let quarters = [
+                grid(column, row),
+                grid(column + 1, row),
+                grid(column, row + 1),
+                grid(column + 1, row + 1),
+            ];
+            *child = self.window(quarters, level - 1, (x % half, y % half), memo);
+        }
+        let node = self.join(children);
+        memo.insert((window, level), node);
+        node
+    }
+
+    /// Are all of the node's live cells in the middle quarter of it (i.e. half as wide as the
+    /// middle half)?
+    fn is_in_middle_quarter(&self, id: NodeId) -> bool {
+        let node = self.node(id);
+        // each quadrant's innermost sixteenth
+        let middle_population: u64 = node
+            .children
+            .iter()
+            .enumerate()
+            .map(|(quadrant, &child)| {
+                let innermost = 3 - quadrant;
+                let grandchild = self.node(child).children[innermost];
+                self.node(self.node(grandchild).children[innermost])
+                    .population
+            })
+            .sum();
+        middle_population == node.population
+    }
+
+    /// The node made of the middle half of the given one.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.node(id).children;
+        let children = [
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        ];
+        self.join(children)
+    }
+
+    /// The node straddling the border between two side by side nodes.
+    fn centre_horizontal(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let ([_, w_ne, _, w_se], [e_nw, _, e_sw, _]) =
+            (self.node(west).children, self.node(east).children);
+        self.join([w_ne, e_nw, w_se, e_sw])
+    }
+
+    /// The node straddling the border between two nodes one above the other.
+    fn centre_vertical(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let ([_, _, n_sw, n_se], [s_nw, s_ne, _, _]) =
+            (self.node(north).children, self.node(south).children);
+        self.join([n_sw, n_se, s_nw, s_ne])
+    }
+
+    /// Work out what the middle half of the given node looks like `2^k` generations later; `k`
+    /// can be at most 2 less than the node's level.
+    fn advance(&mut self, id: NodeId, k: u8) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty_node(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, k)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.advance_by_one(id)
+        } else {
+            // split the node into nine overlapping nodes of half its size...
+            let [nw, ne, sw, se] = node.children;
+            let nine = [
+                nw,
+                self.centre_horizontal(nw, ne),
+                ne,
+                self.centre_vertical(nw, sw),
+                self.centre(id),
+                self.centre_vertical(ne, se),
+                sw,
+                self.centre_horizontal(sw, se),
+                se,
+            ];
+            // ...and either step each of them half way there, or (if we're going less than as far
+            // as a node this size can go) just take their middles...
+            let full_speed = k == node.level - 2;
+            let mut stepped = [DEAD; 9];
+            for (stepped, &part) in stepped.iter_mut().zip(&nine) {
+                *stepped = if full_speed {
+                    self.advance(part, k - 1)
+                } else {
+                    self.centre(part)
+                };
+            }
+            // ...then put those together into four nodes, and step each of those the rest of the
+            // way there
+            let mut result = [DEAD; 4];
+            for (quadrant, result) in result.iter_mut().enumerate() {
+                let (row, column) = (quadrant / 2, quadrant % 2);
+                let corner = row * 3 + column;
+                let quarter = self.join([
+                    stepped[corner],
+                    stepped[corner + 1],
+                    stepped[corner + 3],
+                    stepped[corner + 4],
+                ]);
+                *result = self.advance(quarter, if full_speed { k - 1 } else { k });
+            }
+            self.join(result)
+        };
+        self.results.insert((id, k), result);
+        result
+    }
+
+    /// Work out the middle 2x2 cells of a 4x4 node one generation later, the slow way.
+    fn advance_by_one(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (quadrant, &child) in self.node(id).children.iter().enumerate() {
+            for (cell, &grandchild) in self.node(child).children.iter().enumerate() {
+                let x = (quadrant % 2) * 2 + cell % 2;
+                let y = (quadrant / 2) * 2 + cell / 2;
+                cells[y][x] = grandchild == ALIVE;
+            }
+        }
+        let mut next = [DEAD; 4];
+        for (cell, next) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + cell % 2, 1 + cell / 2);
+            // see `RuleTable` for how neighbourhoods are numbered
+            let mut neighbourhood = 0;
+            for dx in 0..3 {
+                for dy in 0..3 {
+                    if cells[y + dy - 1][x + dx - 1] {
+                        neighbourhood |= 1 << (3 * dx + dy);
+                    }
+                }
+            }
+            *next = if self.table.next_state(neighbourhood) {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+        self.join(next)
+    }
+
+    /// Half the width of the root, i.e. how far it reaches from the origin.
+    fn root_radius(&self) -> i64 {
+        1 << (self.node(self.root).level - 1)
+    }
+
+    /// Find the cell at the given coordinates relative to the top left of the given node.
+    fn cell(&self, id: NodeId, x: u64, y: u64) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return id;
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+        self.cell(node.children[quadrant], x % half, y % half)
+    }
+
+    /// Make a copy of the given node with the cell at the given coordinates (relative to its top
+    /// left) replaced.
+    fn with_cell(&mut self, id: NodeId, x: u64, y: u64, cell: NodeId) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return cell;
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+        let mut children = node.children;
+        children[quadrant] = self.with_cell(children[quadrant], x % half, y % half, cell);
+        self.join(children)
+    }
+
+    /// Add the coordinates of every live cell in the given node, whose top left is at `x`, `y`,
+    /// to `cells`. Empty quadrants are skipped without looking inside them.
+    fn collect_live_cells(&self, id: NodeId, x: i64, y: i64, cells: &mut Vec<(i64, i64)>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x, y));
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        for (quadrant, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((quadrant % 2) as i64 * half, (quadrant / 2) as i64 * half);
+            self.collect_live_cells(child, x + dx, y + dy, cells);
+        }
+    }
+
+    /// How far the nearest live cell in the given node is from one of its edges, where `near`
+    /// are the two quadrants along that edge and `far` are the other two. Results are
+    /// remembered in `memo`, since the same node can turn up in many places.
+    fn distance_to_live_cell(
+        &self,
+        id: NodeId,
+        near: [usize; 2],
+        far: [usize; 2],
+        memo: &mut HashMap<NodeId, Option<u64>>,
+    ) -> Option<u64> {
+        let node = self.node(id);
+        if node.population == 0 {
+            return None;
+        }
+        if node.level == 0 {
+            return Some(0);
+        }
+        if let Some(&distance) = memo.get(&id) {
+            return distance;
+        }
+        let mut nearest = |quadrants: [usize; 2]| {
+            quadrants
+                .iter()
+                .filter_map(|&quadrant| {
+                    self.distance_to_live_cell(node.children[quadrant], near, far, memo)
+                })
+                .min()
+        };
+        let half = 1 << (node.level - 1);
+        let distance = nearest(near).or_else(|| nearest(far).map(|distance| distance + half));
+        memo.insert(id, distance);
+        distance
+    }
+}
+
+impl GameOfLife for GameOfLifeHashLife {
+    /// Cells are never out of bounds, so this always returns `Some`.
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.cell_state_at(x as i64, y as i64)
+            .map(|state| state == 1)
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        self.toggle_cell_at(x as i64, y as i64);
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.set_cell_at(x as i64, y as i64, alive);
+    }
+
+    fn clear(&mut self) {
+        self.root = self.empty_node(GameOfLifeHashLife::MIN_ROOT_LEVEL);
+    }
+
+    fn population(&self) -> usize {
+        self.node(self.root).population as usize
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        let radius = self.root_radius();
+        let mut cells = Vec::new();
+        self.collect_live_cells(self.root, -radius, -radius, &mut cells);
+        Box::new(cells.into_iter())
+    }
+
+    fn tick(&mut self) {
+        self.step_pow2(0);
+    }
+
+    /// Jumps ahead by each power of two in `n` in turn, with `step_pow2`.
+    fn tick_n(&mut self, n: u64) {
+        const MAX_STEP: u8 = GameOfLifeHashLife::MAX_ROOT_LEVEL - 3;
+        for k in (0..64).filter(|k| n >> k & 1 == 1) {
+            // steps too big to take at once are taken as several of the biggest ones
+            let (k, times) = if k <= MAX_STEP {
+                (k, 1)
+            } else {
+                (MAX_STEP, 1 << (k - MAX_STEP))
+            };
+            for _ in 0..times {
+                self.step_pow2(k);
+            }
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn cell_state_at(&self, x: i64, y: i64) -> Option<u8> {
+        let radius = self.root_radius();
+        if x < -radius || y < -radius || x >= radius || y >= radius {
+            return Some(0);
+        }
+        let cell = self.cell(self.root, (x + radius) as u64, (y + radius) as u64);
+        Some((cell == ALIVE) as u8)
+    }
+
+    fn toggle_cell_at(&mut self, x: i64, y: i64) {
+        while {
+            let radius = self.root_radius();
+            x < -radius || y < -radius || x >= radius || y >= radius
+        } {
+            assert!(
+                self.node(self.root).level < GameOfLifeHashLife::MAX_ROOT_LEVEL,
+                "{}, {} is too far from the origin",
+                x,
+                y
+            );
+            self.root = self.expand(self.root);
+        }
+        let radius = self.root_radius();
+        let (x, y) = ((x + radius) as u64, (y + radius) as u64);
+        let cell = if self.cell(self.root, x, y) == ALIVE {
+            DEAD
+        } else {
+            ALIVE
+        };
+        self.root = self.with_cell(self.root, x, y, cell);
+    }
+
+    fn set_cell_at(&mut self, x: i64, y: i64, alive: bool) {
+        if self.cell_state_at(x, y) != Some(alive as u8) {
+            self.toggle_cell_at(x, y);
+        }
+    }
+
+    fn live_bounding_box(&self) -> Option<BoundingBox> {
+        let distance = |near, far| {
+            self.distance_to_live_cell(self.root, near, far, &mut HashMap::new())
+                .map(|distance| distance as i64)
+        };
+        let (left, right) = (distance([0, 2], [1, 3])?, distance([1, 3], [0, 2])?);
+        let (top, bottom) = (distance([0, 1], [2, 3])?, distance([2, 3], [0, 1])?);
+        let (radius, size) = (self.root_radius(), 2 * self.root_radius());
+        Some(BoundingBox {
+            x: left - radius,
+            y: top - radius,
+            width: (size - left - right) as usize,
+            height: (size - top - bottom) as usize,
+        })
+    }
+}
+
+#[cfg(test)]
+mod hashlife_test {
+    use super::{GameOfLife, GameOfLifeHashLife};
+    use crate::format::PatternFormat;
+    use crate::game_of_life::BoundingBox;
+    use crate::game_of_life_unbounded::GameOfLifeUnbounded;
+    use crate::pattern::Pattern;
+    use crate::placement::{Placement, Position};
+
+    const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+    #[test]
+    fn ticks_like_the_unbounded_implementation() {
+        let mut hashlife = GameOfLifeHashLife::new(10, 10);
+        let mut unbounded = GameOfLifeUnbounded::new(10, 10);
+        for &(x, y) in &R_PENTOMINO {
+            hashlife.toggle_cell_at(x - 5, y - 5);
+            unbounded.toggle_cell_at(x - 5, y - 5);
+        }
+        for _ in 0..50 {
+            hashlife.tick();
+            unbounded.tick();
+            assert_eq!(
+                Pattern::from_game(&hashlife),
+                Pattern::from_game(&unbounded)
+            );
+            assert_eq!(hashlife.live_bounding_box(), unbounded.live_bounding_box());
+        }
+    }
+
+    #[test]
+    fn steps_by_powers_of_two() {
+        let mut hashlife = GameOfLifeHashLife::new(10, 10);
+        let mut unbounded = GameOfLifeUnbounded::new(10, 10);
+        for &(x, y) in &R_PENTOMINO {
+            hashlife.toggle_cell_at(x, y);
+            unbounded.toggle_cell_at(x, y);
+        }
+        // 1 + 4 + 128 generations
+        for &k in &[0, 2, 7] {
+            hashlife.step_pow2(k);
+            for _ in 0..1 << k {
+                unbounded.tick();
+            }
+            assert_eq!(
+                Pattern::from_game(&hashlife),
+                Pattern::from_game(&unbounded)
+            );
+        }
+    }
+
+    #[test]
+    fn jumps_far_into_the_future() {
+        let mut game = GameOfLifeHashLife::new(10, 10);
+        // a glider heading down and to the right, which moves one cell diagonally every 4 ticks
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        game.step_pow2(40);
+        let moved = 1 << 38;
+        assert_eq!(
+            game.live_bounding_box(),
+            Some(BoundingBox {
+                x: moved,
+                y: moved,
+                width: 3,
+                height: 3,
+            })
+        );
+        assert_eq!(game.cell_state_at(moved + 1, moved), Some(1));
+    }
+
+    #[test]
+    fn loads_macrocell_files_without_listing_cells() {
+        let glider = "[M2] (golly 2.0)\n$$..*$...*$.***$\n4 0 0 0 1\n";
+        // wherever it's put, it lands in the same place as it would going via a `Pattern`
+        for &(x, y) in &[(0, 0), (5, 7), (-1_000_003, 77)] {
+            let mut game = GameOfLifeHashLife::new(10, 10);
+            game.load_macrocell(glider, x, y).unwrap();
+            let mut expected = GameOfLifeHashLife::new(10, 10);
+            Pattern::parse(glider, PatternFormat::Macrocell)
+                .unwrap()
+                .apply_with(
+                    &mut expected,
+                    &Placement {
+                        position: Position::Offset(x, y),
+                        ..Placement::default()
+                    },
+                );
+            let cells = |game: &GameOfLifeHashLife| {
+                let mut cells: Vec<(i64, i64)> = game.live_cells().collect();
+                cells.sort_unstable();
+                cells
+            };
+            assert_eq!(cells(&game), cells(&expected), "at {}, {}", x, y);
+            assert_eq!(cells(&game)[0], (x, y + 2));
+        }
+
+        // a block in every 8x8 square of a 2^30 cell wide board, which is far too many cells to
+        // list (about 2^56 of them)
+        let mut mc = "[M2] (golly 2.0)\n$$$...**$...**$\n".to_owned();
+        for level in 4..=30 {
+            let child = level - 3;
+            mc += &format!("{} {} {} {} {}\n", level, child, child, child, child);
+        }
+        let mut game = GameOfLifeHashLife::new(10, 10);
+        game.load_macrocell(&mc, 0, 0).unwrap();
+        assert_eq!(game.population(), 1 << 56);
+        assert_eq!(game.cell_state_at(0, 0), Some(1));
+        assert_eq!(game.cell_state_at(2, 0), Some(0));
+        assert_eq!(game.cell_state_at(8, 1), Some(1));
+        // blocks are still lifes
+        game.tick();
+        assert_eq!(game.population(), 1 << 56);
+        assert_eq!(game.cell_state_at((1 << 30) - 7, (1 << 30) - 7), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "grown too far from the origin")]
+    fn stops_before_running_off_the_edge_of_the_coordinates() {
+        let mut game = GameOfLifeHashLife::new(10, 10);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        // the glider would end up around 2^62 cells away, past where an `i64` can reach
+        game.tick_n(u64::MAX);
+    }
+
+    #[test]
+    fn garbage_collection_keeps_the_board() {
+        let mut collected = GameOfLifeHashLife::new(10, 10).with_max_nodes(500);
+        let mut uncollected = GameOfLifeHashLife::new(10, 10);
+        for &(x, y) in &R_PENTOMINO {
+            collected.toggle_cell_at(x, y);
+            uncollected.toggle_cell_at(x, y);
+        }
+        for _ in 0..20 {
+            collected.step_pow2(3);
+            uncollected.step_pow2(3);
+        }
+        assert_eq!(
+            Pattern::from_game(&collected),
+            Pattern::from_game(&uncollected)
+        );
+        assert!(collected.node_count() < uncollected.node_count());
+
+        // collecting by hand leaves just the nodes of the board itself
+        collected.collect_garbage();
+        let population = |game: &GameOfLifeHashLife| game.node(game.root).population;
+        assert_eq!(population(&collected), population(&uncollected));
+        assert!(collected.node_count() < 500);
+    }
+
+    #[test]
+    fn empty_board_has_no_bounding_box() {
+        let mut game = GameOfLifeHashLife::new(10, 10);
+        assert_eq!(game.live_bounding_box(), None);
+        game.toggle_cell(3, 4);
+        game.toggle_cell(3, 4);
+        game.step_pow2(5);
+        assert_eq!(game.live_bounding_box(), None);
+        assert_eq!(game.cell_state_at(-1000, 1000), Some(0));
+    }
+}
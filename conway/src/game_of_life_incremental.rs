@@ -0,0 +1,251 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+// each cell is a byte holding whether it's alive, how many live neighbours it has, and whether
+// it's already on the list of cells to look at this tick
+const ALIVE: u8 = 1;
+const COUNT_SHIFT: u8 = 1;
+const COUNT_MASK: u8 = 0b1111 << COUNT_SHIFT;
+const QUEUED: u8 = 1 << 7;
+
+/// An implementation of GameOfLife that keeps every cell's count of live neighbours up to date
+/// as cells change, instead of counting them all again each tick.
+///
+/// When a cell is born or dies, the counts of its eight neighbours go up or down by one. Only
+/// cells that changed last tick, and their neighbours, can change this tick, so that's all each
+/// tick looks at. On boards where little is happening, that's very little.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeIncremental {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    // the indices of the cells that changed in the last tick (or have been toggled since)
+    changed: Vec<usize>,
+    rule: Rule,
+    topology: Topology,
+}
+
+impl GameOfLifeIncremental {
+    pub fn new(width: usize, height: usize) -> GameOfLifeIncremental {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        GameOfLifeIncremental {
+            width,
+            height,
+            cells: vec![0; width * height],
+            // look at every cell the first time around, in case the rule brings empty space to
+            // life
+            changed: (0..width * height).collect(),
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeIncremental {
+        rule.assert_two_state_totalistic("GameOfLifeIncremental");
+        GameOfLifeIncremental {
+            rule,
+            changed: (0..self.cells.len()).collect(),
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says; panics if any cells are alive.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeIncremental {
+        assert!(
+            self.cells.iter().all(|&cell| cell & ALIVE == 0),
+            "the topology must be set before any cells are brought to life"
+        );
+        GameOfLifeIncremental {
+            topology,
+            changed: (0..self.cells.len()).collect(),
+            ..self
+        }
+    }
+
+    /// How many cells changed in the last tick.
+    pub fn changed_cells(&self) -> usize {
+        self.changed.len()
+    }
+
+    /// The indices of the neighbours of the cell at the given index, and how many of them there
+    /// are (cells by the edges of a plane have fewer than eight).
+    fn neighbours(&self, index: usize) -> ([usize; 8], usize) {
+        let (x, y) = (index % self.width, index / self.width);
+        let inside = x > 0 && y > 0 && x + 1 < self.width && y + 1 < self.height;
+        let mut neighbours = [0; 8];
+        let mut count = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                // cells away from the edges can skip asking the topology
+                let neighbour = if inside {
+                    Some((nx as usize, ny as usize))
+                } else {
+                    self.topology.wrap(nx, ny, self.width, self.height)
+                };
+                if let Some((nx, ny)) = neighbour {
+                    neighbours[count] = nx + ny * self.width;
+                    count += 1;
+                }
+            }
+        }
+        (neighbours, count)
+    }
+
+    /// Bring the cell at the given index to life or kill it, and update its neighbours' counts.
+    fn flip(&mut self, index: usize) {
+        self.cells[index] ^= ALIVE;
+        let alive = self.cells[index] & ALIVE != 0;
+        let (neighbours, count) = self.neighbours(index);
+        for &neighbour in &neighbours[..count] {
+            if alive {
+                self.cells[neighbour] += 1 << COUNT_SHIFT;
+            } else {
+                self.cells[neighbour] -= 1 << COUNT_SHIFT;
+            }
+        }
+    }
+}
+
+impl GameOfLife for GameOfLifeIncremental {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        if x < self.width && y < self.height {
+            Some(self.cells[x + y * self.width] & ALIVE != 0)
+        } else {
+            None
+        }
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            let index = x + y * self.width;
+            self.flip(index);
+            self.changed.push(index);
+        }
+    }
+
+    fn tick(&mut self) {
+        // only the cells that changed and their neighbours can change now; the queued flag stops
+        // cells next to several changes from being looked at more than once
+        let mut to_check = Vec::new();
+        for &index in &self.changed {
+            let (neighbours, count) = self.neighbours(index);
+            for &cell in neighbours[..count].iter().chain(Some(&index)) {
+                if self.cells[cell] & QUEUED == 0 {
+                    self.cells[cell] |= QUEUED;
+                    to_check.push(cell);
+                }
+            }
+        }
+
+        // work out every change before making any, since making them changes the counts
+        let mut changed = Vec::new();
+        for index in to_check {
+            let cell = self.cells[index] & !QUEUED;
+            self.cells[index] = cell;
+            let alive = cell & ALIVE != 0;
+            let live_neighbours = ((cell & COUNT_MASK) >> COUNT_SHIFT) as u32;
+            if self.rule.next_state(alive, live_neighbours) != alive {
+                changed.push(index);
+            }
+        }
+        for &index in &changed {
+            self.flip(index);
+        }
+        self.changed = changed;
+    }
+
+    /// Stops early once a tick changes nothing, since then nothing will ever change again.
+    fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+            if self.changed.is_empty() {
+                break;
+            }
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn tick_report(&self) -> Option<String> {
+        Some(format!("{} cells changed", self.changed_cells()))
+    }
+}
+
+#[cfg(test)]
+mod incremental_test {
+    use super::{GameOfLife, GameOfLifeIncremental};
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, live_cells, scatter_cells};
+    use crate::topology::{Edges, Topology};
+
+    #[test]
+    fn only_blinker_cells_change() {
+        let mut game = GameOfLifeIncremental::new(20, 20);
+        for x in 2..5 {
+            game.toggle_cell(x, 3);
+        }
+        for &(x, y) in &[(15, 15), (16, 15), (15, 16), (16, 16)] {
+            game.toggle_cell(x, y);
+        }
+        game.tick();
+        game.tick();
+        // two cells die at the ends of the blinker and two are born, while the block sits still
+        assert_eq!(game.changed_cells(), 4);
+        assert_eq!(game.tick_report().as_deref(), Some("4 cells changed"));
+        assert_eq!(
+            live_cells(&game),
+            vec![
+                (2, 3),
+                (3, 3),
+                (4, 3),
+                (15, 15),
+                (16, 15),
+                (15, 16),
+                (16, 16)
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_like_the_solution() {
+        let (width, height) = (40, 23);
+        let topologies = [
+            Topology::Plane,
+            Topology::Torus,
+            Topology::KleinBottle {
+                twisted: Edges::Vertical,
+            },
+            Topology::CrossSurface,
+        ];
+        for &rule in &["B3/S23", "B36/S23", "B0/S8", "B2/S"] {
+            for &topology in &topologies {
+                let rule: Rule = rule.parse().unwrap();
+                let mut incremental = GameOfLifeIncremental::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology);
+                scatter_cells(&mut incremental, 2468);
+                assert_runs_like_the_solution(
+                    &mut incremental,
+                    rule,
+                    topology,
+                    30,
+                    Some(7),
+                    "incremental",
+                );
+            }
+        }
+    }
+}
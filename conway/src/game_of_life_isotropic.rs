@@ -0,0 +1,169 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::{Rule, RuleTable};
+use crate::topology::Topology;
+
+/// An implementation of GameOfLife that supports "isotropic non-totalistic" rules written in
+/// Hensel notation, such as `B2-a/S12`, where it matters which neighbours are alive and not just
+/// how many.
+///
+/// Rather than counting neighbours, it looks up each cell's whole 3x3 neighbourhood in a table of
+/// what happens next (see `RuleTable`), so it runs ordinary rules just as well.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeIsotropic {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+    table: RuleTable,
+    topology: Topology,
+}
+
+impl GameOfLifeIsotropic {
+    pub fn new(width: usize, height: usize) -> GameOfLifeIsotropic {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        GameOfLifeIsotropic {
+            width,
+            height,
+            cells: vec![false; width * height],
+            table: Rule::conway().lookup_table(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given two state rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeIsotropic {
+        rule.assert_two_state("GameOfLifeIsotropic");
+        GameOfLifeIsotropic {
+            table: rule.lookup_table(),
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeIsotropic {
+        GameOfLifeIsotropic { topology, ..self }
+    }
+
+    /// The column of three cells centred on the given row, as the top three bits of a
+    /// neighbourhood (see `RuleTable`). The column can be off the edge of the board, in which case
+    /// its cells are wherever the topology wraps them around to.
+    fn column_bits(&self, x: isize, y: usize) -> usize {
+        (0..3).fold(0, |bits, dy| {
+            let cell = self
+                .topology
+                .wrap(x, y as isize + dy - 1, self.width, self.height);
+            let alive = cell.is_some_and(|(x, y)| self.cells[x + y * self.width]);
+            bits | (alive as usize) << (6 + dy)
+        })
+    }
+}
+
+impl GameOfLife for GameOfLifeIsotropic {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        if x < self.width && y < self.height {
+            Some(self.cells[x + y * self.width])
+        } else {
+            None
+        }
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] ^= true;
+        }
+    }
+
+    fn tick(&mut self) {
+        let mut next = vec![false; self.cells.len()];
+        for y in 0..self.height {
+            // slide the neighbourhood along the row a column at a time, starting with the column
+            // off the left edge of the board and the first column in it
+            let mut neighbourhood = self.column_bits(-1, y) >> 3 | self.column_bits(0, y);
+            for x in 0..self.width {
+                neighbourhood = neighbourhood >> 3 | self.column_bits(x as isize + 1, y);
+                next[x + y * self.width] = self.table.next_state(neighbourhood);
+            }
+        }
+        self.cells = next;
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod isotropic_test {
+    use super::{GameOfLife, GameOfLifeIsotropic};
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, live_cells};
+    use crate::topology::{Edges, Topology};
+
+    #[test]
+    fn runs_conway_like_the_solution() {
+        let mut isotropic = GameOfLifeIsotropic::new(12, 10);
+        // an R-pentomino, which is plenty chaotic, right by the edge
+        for &(x, y) in &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+            isotropic.toggle_cell(x, y);
+        }
+        assert_runs_like_the_solution(
+            &mut isotropic,
+            Rule::conway(),
+            Topology::Plane,
+            20,
+            None,
+            "isotropic",
+        );
+    }
+
+    #[test]
+    fn runs_like_the_solution_on_other_topologies() {
+        for &topology in &[
+            Topology::Torus,
+            Topology::TwistedTorus {
+                edges: Edges::Vertical,
+                shift: 3,
+            },
+            Topology::KleinBottle {
+                twisted: Edges::Horizontal,
+            },
+            Topology::CrossSurface,
+        ] {
+            let mut isotropic = GameOfLifeIsotropic::new(12, 10).with_topology(topology);
+            for &(x, y) in &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+                isotropic.toggle_cell(x, y);
+            }
+            assert_runs_like_the_solution(
+                &mut isotropic,
+                Rule::conway(),
+                topology,
+                30,
+                None,
+                "isotropic",
+            );
+        }
+    }
+
+    #[test]
+    fn births_depend_on_neighbour_arrangement() {
+        let b2_not_a = "B2-a/S".parse().unwrap();
+        // the cells in between these two have them on opposite sides (2i) or as the corners of
+        // one side (2c), so are born
+        let mut game = GameOfLifeIsotropic::new(5, 3).with_rule(b2_not_a);
+        game.toggle_cell(1, 1);
+        game.toggle_cell(3, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(2, 0), (2, 1), (2, 2)]);
+
+        // whereas the cells above and below these two have them right next to each other (2a)
+        let mut game = GameOfLifeIsotropic::new(5, 3).with_rule(b2_not_a);
+        game.toggle_cell(1, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![]);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::game_of_life::GameOfLife;
+use crate::larger_than_life::{LargerThanLifeRule, Neighbourhood};
+use crate::topology::Topology;
+
+/// An implementation of GameOfLife for "Larger than Life" rules (see `LargerThanLifeRule`), where
+/// cells can have hundreds of neighbours.
+///
+/// Counting that many neighbours one by one for every cell would be painfully slow, so each tick
+/// starts by building a summed-area table: for every position, how many live cells there are
+/// above and to the left of it. The number of live cells in any rectangle can then be worked out
+/// from just four entries of the table. For Moore neighbourhoods that gives each cell's count
+/// straight away, and for the other shapes it's added up a row at a time.
+///
+/// The table covers a border as wide as the rule's range around the board too, filled in with
+/// whatever cells the topology wraps those positions around to, so neighbourhoods never need
+/// clipping to the edges of the board.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeLargerThanLife {
+    width: usize,
+    height: usize,
+    // 0 for dead, 1 for alive, and 2 and up for dying; see `GameOfLife::cell_state`
+    cells: Vec<u8>,
+    rule: LargerThanLifeRule,
+    // see `LargerThanLifeRule::row_extents`
+    row_extents: Vec<usize>,
+    topology: Topology,
+}
+
+impl GameOfLifeLargerThanLife {
+    pub fn new(width: usize, height: usize) -> GameOfLifeLargerThanLife {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        let rule = LargerThanLifeRule::default();
+        GameOfLifeLargerThanLife {
+            width,
+            height,
+            cells: vec![0; width * height],
+            row_extents: rule.row_extents(),
+            rule,
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given rule instead of Conway's Game of Life.
+    pub fn with_rule(self, rule: LargerThanLifeRule) -> GameOfLifeLargerThanLife {
+        GameOfLifeLargerThanLife {
+            row_extents: rule.row_extents(),
+            rule,
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeLargerThanLife {
+        GameOfLifeLargerThanLife { topology, ..self }
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    /// How many entries there are in each row of the summed-area table.
+    fn stride(&self) -> usize {
+        self.width + 2 * self.rule.range() + 1
+    }
+
+    /// Build the summed-area table of live cells, including a border `range` cells wide around
+    /// the board: the entry at `x + y * stride` is the number of live cells in the bordered board
+    /// with coordinates less than `x` and `y`, so the table has an extra row and column of zeroes
+    /// at the top and left.
+    fn summed_area_table(&self) -> Vec<usize> {
+        let range = self.rule.range();
+        let stride = self.stride();
+        let mut table = vec![0; stride * (self.height + 2 * range + 1)];
+        for y in 0..self.height + 2 * range {
+            let mut row_total = 0;
+            for x in 0..stride - 1 {
+                let cell = self.topology.wrap(
+                    x as isize - range as isize,
+                    y as isize - range as isize,
+                    self.width,
+                    self.height,
+                );
+                if let Some((x, y)) = cell {
+                    row_total += (self.cells[x + y * self.width] == 1) as usize;
+                }
+                table[(x + 1) + (y + 1) * stride] = table[(x + 1) + y * stride] + row_total;
+            }
+        }
+        table
+    }
+
+    /// Count the live cells in the neighbourhood of the given cell, using the summed-area table.
+    fn live_neighbours(&self, table: &[usize], x: usize, y: usize) -> usize {
+        let stride = self.stride();
+        // the number of live cells in the rectangle from `left` to `right` and `top` to `bottom`
+        // (all inclusive) of the bordered board
+        let rectangle = |left: usize, right: usize, top: usize, bottom: usize| {
+            let (right, bottom) = (right + 1, bottom + 1);
+            table[right + bottom * stride] + table[left + top * stride]
+                - table[left + bottom * stride]
+                - table[right + top * stride]
+        };
+
+        // on the bordered board, the cell is at `x + range`, `y + range`, so its neighbourhood
+        // starts at `x`, `y`
+        let range = self.rule.range();
+        let mut count = match self.rule.neighbourhood() {
+            Neighbourhood::Moore => rectangle(x, x + 2 * range, y, y + 2 * range),
+            _ => (0..=2 * range)
+                .map(|dy| {
+                    let extent = self.row_extents[dy];
+                    rectangle(x + range - extent, x + range + extent, y + dy, y + dy)
+                })
+                .sum(),
+        };
+        if !self.rule.includes_middle() && self.cells[x + y * self.width] == 1 {
+            count -= 1;
+        }
+        count
+    }
+}
+
+impl GameOfLife for GameOfLifeLargerThanLife {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.cell_state(x, y).map(|state| state == 1)
+    }
+
+    fn cell_state(&self, x: usize, y: usize) -> Option<u8> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    fn num_states(&self) -> u8 {
+        self.rule.states()
+    }
+
+    /// Kill live cells, and bring dead or dying cells to life.
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if let Some(i) = self.index(x, y) {
+            self.cells[i] = if self.cells[i] == 1 { 0 } else { 1 };
+        }
+    }
+
+    fn tick(&mut self) {
+        let table = self.summed_area_table();
+        let mut next = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let state = self.cells[x + y * self.width];
+                next.push(
+                    self.rule
+                        .next_state(state, self.live_neighbours(&table, x, y)),
+                );
+            }
+        }
+        self.cells = next;
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod larger_than_life_game_test {
+    use super::{GameOfLife, GameOfLifeLargerThanLife};
+    use crate::game_of_life_solution::GameOfLifeSolution;
+    use crate::larger_than_life::LargerThanLifeRule;
+    use crate::topology::{Edges, Topology};
+
+    /// Count the neighbours of every cell of a game the slow way, for comparison.
+    fn brute_force_counts(game: &GameOfLifeLargerThanLife) -> Vec<usize> {
+        let extents = game.rule.row_extents();
+        let range = game.rule.range() as isize;
+        let mut counts = Vec::new();
+        for y in 0..game.height() as isize {
+            for x in 0..game.width() as isize {
+                let mut count = 0;
+                for dy in -range..=range {
+                    let extent = extents[(dy + range) as usize] as isize;
+                    for dx in -extent..=extent {
+                        let middle = dx == 0 && dy == 0;
+                        let neighbour =
+                            game.topology
+                                .wrap(x + dx, y + dy, game.width(), game.height());
+                        if let Some((nx, ny)) = neighbour {
+                            if (!middle || game.rule.includes_middle())
+                                && game.is_cell_alive(nx, ny) == Some(true)
+                            {
+                                count += 1;
+                            }
+                        }
+                    }
+                }
+                counts.push(count);
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn summed_area_counts_match_brute_force() {
+        let rules_and_topologies = [
+            ("R3,C0,M1,S2..5,B3..4,NM", Topology::Plane),
+            ("R3,C0,M0,S2..5,B3..4,NN", Topology::Plane),
+            ("R4,C0,M1,S2..5,B3..4,NC", Topology::Plane),
+            ("R3,C0,M1,S2..5,B3..4,NM", Topology::Torus),
+            ("R12,C0,M0,S2..5,B3..4,NN", Topology::CrossSurface),
+            (
+                "R4,C0,M1,S2..5,B3..4,NC",
+                Topology::TwistedTorus {
+                    edges: Edges::Horizontal,
+                    shift: -4,
+                },
+            ),
+        ];
+        for &(rule, topology) in &rules_and_topologies {
+            let mut game = GameOfLifeLargerThanLife::new(13, 11)
+                .with_rule(rule.parse().unwrap())
+                .with_topology(topology);
+            // a scattering of cells, some of them near the edges
+            for i in 0..40 {
+                game.toggle_cell((i * 7) % 13, (i * 5) % 11);
+            }
+            let table = game.summed_area_table();
+            let counts: Vec<_> = (0..game.height())
+                .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+                .map(|(x, y)| game.live_neighbours(&table, x, y))
+                .collect();
+            assert_eq!(
+                counts,
+                brute_force_counts(&game),
+                "rule {} on a {}",
+                rule,
+                topology
+            );
+        }
+    }
+
+    #[test]
+    fn runs_conway_like_the_solution() {
+        let mut ltl =
+            GameOfLifeLargerThanLife::new(12, 10).with_rule(LargerThanLifeRule::default());
+        let mut solution = GameOfLifeSolution::new(12, 10);
+        for &(x, y) in &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+            ltl.toggle_cell(x, y);
+            solution.toggle_cell(x, y);
+        }
+        for _ in 0..20 {
+            ltl.tick();
+            solution.tick();
+            for y in 0..10 {
+                for x in 0..12 {
+                    assert_eq!(ltl.is_cell_alive(x, y), solution.is_cell_alive(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dying_cells_count_down() {
+        let mut game =
+            GameOfLifeLargerThanLife::new(5, 5).with_rule("R2,C4,S10..20,B10..20".parse().unwrap());
+        assert_eq!(game.num_states(), 4);
+        game.toggle_cell(2, 2);
+        game.tick();
+        assert_eq!(game.cell_state(2, 2), Some(2));
+        game.tick();
+        assert_eq!(game.cell_state(2, 2), Some(3));
+        game.tick();
+        assert_eq!(game.cell_state(2, 2), Some(0));
+    }
+}
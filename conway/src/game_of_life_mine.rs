@@ -55,8 +55,8 @@ mod broken_game_test {
     #[test]
     fn some_var_is_always_true() {
         let some_var = true;
-        assert_eq!(
-            some_var, true,
+        assert!(
+            some_var,
             "some_var must always be true, otherwise the universe is not in order"
         );
     }
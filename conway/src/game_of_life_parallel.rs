@@ -0,0 +1,243 @@
+use crate::game_of_life::GameOfLife;
+use crate::game_of_life_bit_packed::GameOfLifeBitPacked;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// An implementation of GameOfLife that spreads the work of each tick over a pool of threads, for
+/// big boards.
+///
+/// The board is stored the same way as `GameOfLifeBitPacked`, and each tick it's split into
+/// horizontal bands of rows, one per thread. Every thread reads the whole of the current board
+/// (including the rows just outside its band, which belong to its neighbours) but only works out
+/// its own band of the next board, so the threads never have to wait for each other.
+///
+/// The threads are started when the game is made and wait between ticks, so a tick doesn't pay
+/// for starting them. Clones of the game share the same threads.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeParallel {
+    // shared with the threads while they work out a tick
+    board: Arc<GameOfLifeBitPacked>,
+    workers: Arc<Mutex<Workers>>,
+}
+
+impl GameOfLifeParallel {
+    /// Make a game that uses as many threads as the computer can run at once.
+    pub fn new(width: usize, height: usize) -> GameOfLifeParallel {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        GameOfLifeParallel {
+            board: Arc::new(GameOfLifeBitPacked::new(width, height)),
+            workers: Arc::new(Mutex::new(Workers::start(threads))),
+        }
+    }
+
+    /// Use the given number of threads to work out each tick.
+    pub fn with_threads(self, threads: usize) -> GameOfLifeParallel {
+        assert!(threads > 0, "there must be at least one thread");
+        GameOfLifeParallel {
+            workers: Arc::new(Mutex::new(Workers::start(threads))),
+            ..self
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeParallel {
+        GameOfLifeParallel {
+            board: Arc::new(Arc::unwrap_or_clone(self.board).with_rule(rule)),
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeParallel {
+        GameOfLifeParallel {
+            board: Arc::new(Arc::unwrap_or_clone(self.board).with_topology(topology)),
+            ..self
+        }
+    }
+
+    /// How many threads each tick is split between.
+    pub fn threads(&self) -> usize {
+        let workers = self
+            .workers
+            .lock()
+            .expect("a tick panicked part way through");
+        workers.bands.len()
+    }
+}
+
+/// A band of rows for one of the threads to work out the next state of.
+struct Band {
+    board: Arc<GameOfLifeBitPacked>,
+    // where row 0 is the border above the board
+    first_row: usize,
+    rows: usize,
+}
+
+/// The threads working out the bands, which wait for a band to be sent to them and send back the
+/// band's first row and its next state.
+#[derive(Debug)]
+struct Workers {
+    bands: Vec<Sender<Band>>,
+    next_rows: Receiver<(usize, Vec<u64>)>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    fn start(threads: usize) -> Workers {
+        let (send_next_rows, next_rows) = mpsc::channel();
+        let (bands, threads) = (0..threads)
+            .map(|_| {
+                let (send_band, bands) = mpsc::channel::<Band>();
+                let send_next_rows = send_next_rows.clone();
+                let thread = thread::spawn(move || {
+                    for band in bands {
+                        let mut next = vec![0; band.rows * band.board.words_per_row()];
+                        band.board.next_rows(band.first_row, &mut next);
+                        // let go of the board before sending the rows back, so that once the
+                        // tick has every band back it's the only one holding the board, and can
+                        // change it without copying it
+                        drop(band.board);
+                        if send_next_rows.send((band.first_row, next)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                (send_band, thread)
+            })
+            .unzip();
+        Workers {
+            bands,
+            next_rows,
+            threads,
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        // with nothing left to send them bands, the threads stop
+        self.bands.clear();
+        for thread in self.threads.drain(..) {
+            // a thread that panicked will already have made a tick panic, so there's nothing more
+            // to do about it here
+            let _ = thread.join();
+        }
+    }
+}
+
+impl GameOfLife for GameOfLifeParallel {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.board.is_cell_alive(x, y)
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        Arc::make_mut(&mut self.board).toggle_cell(x, y);
+    }
+
+    fn tick(&mut self) {
+        let workers = self
+            .workers
+            .lock()
+            .expect("a tick panicked part way through");
+        GameOfLifeBitPacked::tick_shared(&mut self.board, |board, next| {
+            let (words, height) = (board.words_per_row(), board.height());
+            let rows_per_band = height.div_ceil(workers.bands.len());
+            let mut bands = 0;
+            // there can be more threads than rows, which leaves some with nothing to do
+            for (first_row, thread) in (1..=height).step_by(rows_per_band).zip(&workers.bands) {
+                let band = Band {
+                    board: Arc::clone(board),
+                    first_row,
+                    rows: rows_per_band.min(height + 1 - first_row),
+                };
+                thread.send(band).expect("a worker thread panicked");
+                bands += 1;
+            }
+            for _ in 0..bands {
+                let (first_row, rows) = workers.next_rows.recv().expect("a worker thread panicked");
+                // `next` starts at row 1, just below the border
+                next[(first_row - 1) * words..][..rows.len()].copy_from_slice(&rows);
+            }
+        });
+    }
+
+    fn width(&self) -> usize {
+        self.board.width()
+    }
+
+    fn height(&self) -> usize {
+        self.board.height()
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        Arc::make_mut(&mut self.board).set_cell(x, y, alive);
+    }
+
+    fn clear(&mut self) {
+        Arc::make_mut(&mut self.board).clear();
+    }
+
+    fn population(&self) -> usize {
+        self.board.population()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        self.board.live_cells()
+    }
+}
+
+#[cfg(test)]
+mod parallel_test {
+    use super::GameOfLifeParallel;
+    use crate::game_of_life::GameOfLife;
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, live_cells, scatter_cells};
+    use crate::topology::Topology;
+
+    #[test]
+    fn runs_like_the_solution_with_any_number_of_threads() {
+        let (width, height) = (70, 13);
+        // including more threads than there are rows, which leaves some with nothing to do
+        for &threads in &[1, 2, 3, 5, 13, 20] {
+            for &topology in &[Topology::Plane, Topology::Torus] {
+                let mut parallel = GameOfLifeParallel::new(width, height)
+                    .with_threads(threads)
+                    .with_topology(topology);
+                scatter_cells(&mut parallel, 54321);
+                assert_runs_like_the_solution(
+                    &mut parallel,
+                    Rule::conway(),
+                    topology,
+                    10,
+                    None,
+                    &format!("{} threads", threads),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clones_share_threads_but_not_cells() {
+        let mut game = GameOfLifeParallel::new(40, 30).with_threads(3);
+        scatter_cells(&mut game, 2024);
+        let mut clone = game.clone();
+        let before = live_cells(&game);
+
+        clone.tick();
+        clone.toggle_cell(0, 0);
+        assert_eq!(
+            live_cells(&game),
+            before,
+            "ticking a clone changed the original"
+        );
+
+        game.tick();
+        game.toggle_cell(0, 0);
+        assert_eq!(live_cells(&game), live_cells(&clone));
+        assert_eq!(clone.threads(), 3);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use std::fmt;
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Which CPU instructions `GameOfLifeSimd` adds up neighbours with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionSet {
+    /// Plain Rust, one cell at a time, which works on any CPU.
+    Portable,
+    /// x86 SSE2 instructions, adding up 16 cells at a time.
+    Sse2,
+    /// x86 AVX2 instructions, adding up 32 cells at a time.
+    Avx2,
+}
+
+impl InstructionSet {
+    /// The fastest instruction set this CPU supports.
+    pub fn detect() -> InstructionSet {
+        [InstructionSet::Avx2, InstructionSet::Sse2]
+            .iter()
+            .copied()
+            .find(|set| set.is_supported())
+            .unwrap_or(InstructionSet::Portable)
+    }
+
+    /// Whether this CPU has the instructions.
+    pub fn is_supported(self) -> bool {
+        match self {
+            InstructionSet::Portable => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            InstructionSet::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            InstructionSet::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            InstructionSet::Portable => "portable code",
+            InstructionSet::Sse2 => "SSE2",
+            InstructionSet::Avx2 => "AVX2",
+        })
+    }
+}
+
+/// An implementation of GameOfLife that adds up neighbours with SIMD instructions, which add up
+/// a whole run of cells at once.
+///
+/// Each cell is a byte that's 1 if it's alive and 0 if it's dead, so a cell's neighbour count is
+/// the sum of the bytes around it. For a row of cells, that's the sum of eight runs of bytes (the
+/// row above shifted left, not shifted and shifted right, and so on), which SSE2 can add 16 bytes
+/// at a time and AVX2 32 at a time. Which instructions to use is worked out when the program runs,
+/// so the same program works on any CPU.
+///
+/// Like `GameOfLifeBitPacked`, there's an extra cell at each end of each row, and an extra row
+/// above and below the board, holding whatever the topology says is past the edges.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeSimd {
+    width: usize,
+    height: usize,
+    // the cells of the board, including the border, where cell `x`, `y` is at
+    // `x + 1 + (y + 1) * (width + 2)`
+    cells: Vec<u8>,
+    // the next state of a cell, indexed by `alive * 9 + live_neighbours`
+    next_state: [u8; 18],
+    topology: Topology,
+    instruction_set: InstructionSet,
+}
+
+impl GameOfLifeSimd {
+    /// Make a game that uses the fastest instructions this CPU supports.
+    pub fn new(width: usize, height: usize) -> GameOfLifeSimd {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        GameOfLifeSimd {
+            width,
+            height,
+            cells: vec![0; (width + 2) * (height + 2)],
+            next_state: GameOfLifeSimd::next_states(&Rule::conway()),
+            topology: Topology::Plane,
+            instruction_set: InstructionSet::detect(),
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeSimd {
+        rule.assert_two_state_totalistic("GameOfLifeSimd");
+        GameOfLifeSimd {
+            next_state: GameOfLifeSimd::next_states(&rule),
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeSimd {
+        GameOfLifeSimd { topology, ..self }
+    }
+
+    /// Add up neighbours with the given instructions instead of the fastest ones.
+    ///
+    /// Panics if this CPU doesn't support them.
+    pub fn with_instruction_set(self, instruction_set: InstructionSet) -> GameOfLifeSimd {
+        assert!(
+            instruction_set.is_supported(),
+            "this CPU doesn't support {}",
+            instruction_set
+        );
+        GameOfLifeSimd {
+            instruction_set,
+            ..self
+        }
+    }
+
+    /// Which instructions neighbours are added up with.
+    pub fn instruction_set(&self) -> InstructionSet {
+        self.instruction_set
+    }
+
+    fn next_states(rule: &Rule) -> [u8; 18] {
+        let mut next_state = [0; 18];
+        for (i, state) in next_state.iter_mut().enumerate() {
+            *state = rule.next_state(i >= 9, i as u32 % 9) as u8;
+        }
+        next_state
+    }
+
+    fn index(&self, x: isize, y: isize) -> usize {
+        (x + 1) as usize + (y + 1) as usize * (self.width + 2)
+    }
+
+    /// Fill in the border around the board with the cells the topology says are there. On a plane
+    /// they're always dead, so there's nothing to do.
+    fn refresh_border(&mut self) {
+        if self.topology == Topology::Plane {
+            return;
+        }
+        let (width, height) = (self.width as isize, self.height as isize);
+        let border = (-1..=width)
+            .flat_map(|x| vec![(x, -1), (x, height)])
+            .chain((0..height).flat_map(|y| vec![(-1, y), (width, y)]));
+        for (x, y) in border {
+            let cell = match self.topology.wrap(x, y, self.width, self.height) {
+                Some((x, y)) => self.cells[self.index(x as isize, y as isize)],
+                None => 0,
+            };
+            let index = self.index(x, y);
+            self.cells[index] = cell;
+        }
+    }
+}
+
+/// Add up the neighbours of each cell in a row, one cell at a time. `above`, `this` and `below`
+/// are the rows including their border cells, so they're two cells longer than `counts`.
+fn sum_row_portable(above: &[u8], this: &[u8], below: &[u8], counts: &mut [u8]) {
+    for (i, count) in counts.iter_mut().enumerate() {
+        *count = above[i]
+            + above[i + 1]
+            + above[i + 2]
+            + this[i]
+            + this[i + 2]
+            + below[i]
+            + below[i + 1]
+            + below[i + 2];
+    }
+}
+
+/// Like `sum_row_portable`, but 16 cells at a time, with whatever's left over done one at a time.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn sum_row_sse2(above: &[u8], this: &[u8], below: &[u8], counts: &mut [u8]) {
+    const LANES: usize = 16;
+    let mut i = 0;
+    while i + LANES <= counts.len() {
+        // the furthest byte read is at `i + 2 + 15`, which is still inside the row's border
+        let load = |row: &[u8], offset: usize| {
+            _mm_loadu_si128(row[i + offset..i + offset + LANES].as_ptr() as *const __m128i)
+        };
+        let sum = _mm_add_epi8(
+            _mm_add_epi8(
+                _mm_add_epi8(load(above, 0), load(above, 1)),
+                _mm_add_epi8(load(above, 2), load(this, 0)),
+            ),
+            _mm_add_epi8(
+                _mm_add_epi8(load(this, 2), load(below, 0)),
+                _mm_add_epi8(load(below, 1), load(below, 2)),
+            ),
+        );
+        _mm_storeu_si128(counts[i..i + LANES].as_mut_ptr() as *mut __m128i, sum);
+        i += LANES;
+    }
+    sum_row_portable(&above[i..], &this[i..], &below[i..], &mut counts[i..]);
+}
+
+/// Like `sum_row_portable`, but 32 cells at a time, with whatever's left over done one at a time.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn sum_row_avx2(above: &[u8], this: &[u8], below: &[u8], counts: &mut [u8]) {
+    const LANES: usize = 32;
+    let mut i = 0;
+    while i + LANES <= counts.len() {
+        // the furthest byte read is at `i + 2 + 31`, which is still inside the row's border
+        let load = |row: &[u8], offset: usize| {
+            _mm256_loadu_si256(row[i + offset..i + offset + LANES].as_ptr() as *const __m256i)
+        };
+        let sum = _mm256_add_epi8(
+            _mm256_add_epi8(
+                _mm256_add_epi8(load(above, 0), load(above, 1)),
+                _mm256_add_epi8(load(above, 2), load(this, 0)),
+            ),
+            _mm256_add_epi8(
+                _mm256_add_epi8(load(this, 2), load(below, 0)),
+                _mm256_add_epi8(load(below, 1), load(below, 2)),
+            ),
+        );
+        _mm256_storeu_si256(counts[i..i + LANES].as_mut_ptr() as *mut __m256i, sum);
+        i += LANES;
+    }
+    sum_row_portable(&above[i..], &this[i..], &below[i..], &mut counts[i..]);
+}
+
+impl GameOfLife for GameOfLifeSimd {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        if x < self.width && y < self.height {
+            Some(self.cells[self.index(x as isize, y as isize)] != 0)
+        } else {
+            None
+        }
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            let index = self.index(x as isize, y as isize);
+            self.cells[index] ^= 1;
+        }
+    }
+
+    fn tick(&mut self) {
+        self.refresh_border();
+        let stride = self.width + 2;
+        let mut next = vec![0; self.cells.len()];
+        let mut counts = vec![0; self.width];
+        for row in 1..=self.height {
+            let (above, this, below) = (
+                &self.cells[(row - 1) * stride..row * stride],
+                &self.cells[row * stride..(row + 1) * stride],
+                &self.cells[(row + 1) * stride..(row + 2) * stride],
+            );
+            match self.instruction_set {
+                InstructionSet::Portable => sum_row_portable(above, this, below, &mut counts),
+                // safe because the instruction set was checked when it was chosen
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                InstructionSet::Sse2 => unsafe { sum_row_sse2(above, this, below, &mut counts) },
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                InstructionSet::Avx2 => unsafe { sum_row_avx2(above, this, below, &mut counts) },
+                #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+                _ => unreachable!("{} isn't supported here", self.instruction_set),
+            }
+            let next_row = &mut next[row * stride + 1..(row + 1) * stride - 1];
+            for ((next, &alive), &count) in next_row.iter_mut().zip(&this[1..]).zip(&counts) {
+                *next = self.next_state[(alive * 9 + count) as usize];
+            }
+        }
+        self.cells = next;
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+mod simd_test {
+    use super::{GameOfLifeSimd, InstructionSet};
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, scatter_cells};
+    use crate::topology::{Edges, Topology};
+
+    #[test]
+    fn picks_a_supported_instruction_set() {
+        let game = GameOfLifeSimd::new(10, 10);
+        assert!(game.instruction_set().is_supported());
+        assert_eq!(game.instruction_set(), InstructionSet::detect());
+    }
+
+    #[test]
+    fn runs_like_the_solution_on_random_boards() {
+        let instruction_sets = [
+            InstructionSet::Portable,
+            InstructionSet::Sse2,
+            InstructionSet::Avx2,
+        ];
+        let topologies = [
+            Topology::Plane,
+            Topology::Torus,
+            Topology::KleinBottle {
+                twisted: Edges::Horizontal,
+            },
+        ];
+        // sizes that leave some cells over after the 16 and 32 cell runs, or have none at all
+        for &(width, height) in &[(7, 5), (16, 9), (75, 40)] {
+            for &rule in &["B3/S23", "B36/S23", "B0/S8"] {
+                for &topology in &topologies {
+                    let rule: Rule = rule.parse().unwrap();
+                    for &set in instruction_sets.iter().filter(|set| set.is_supported()) {
+                        let mut game = GameOfLifeSimd::new(width, height)
+                            .with_rule(rule)
+                            .with_topology(topology)
+                            .with_instruction_set(set);
+                        scatter_cells(&mut game, (width * height) as u32);
+                        assert_runs_like_the_solution(
+                            &mut game,
+                            rule,
+                            topology,
+                            10,
+                            None,
+                            &format!("{} on a {}x{} board", set, width, height),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -95,20 +95,39 @@
 //! (In case you're wondering, `//!` is how you do module level documentation in Rust)
 
 use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
 
 #[derive(Clone, Debug)]
 pub struct GameOfLifeSolution {
     width: usize,
     playground: Vec<bool>,
+    rule: Rule,
+    topology: Topology,
 }
 
 impl GameOfLifeSolution {
     pub fn new(width: usize, height: usize) -> GameOfLifeSolution {
         println!("width is {} and height is {}", width, height);
-        let mut playground = Vec::new();
-        playground.extend(::std::iter::repeat(false).take(width * height));
+        let playground = vec![false; width * height];
 
-        GameOfLifeSolution { width, playground }
+        GameOfLifeSolution {
+            width,
+            playground,
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeSolution {
+        rule.assert_two_state_totalistic("GameOfLifeSolution");
+        GameOfLifeSolution { rule, ..self }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeSolution {
+        GameOfLifeSolution { topology, ..self }
     }
 
     fn get_cell_mut(&mut self, x: usize, y: usize) -> Option<&mut bool> {
@@ -123,7 +142,7 @@ impl GameOfLifeSolution {
 impl GameOfLife for GameOfLifeSolution {
     fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
         if x < self.width() && y < self.height() {
-            Some(self.playground[(x + y * self.width) as usize])
+            Some(self.playground[x + y * self.width])
         } else {
             None
         }
@@ -143,40 +162,33 @@ impl GameOfLife for GameOfLifeSolution {
             let x = u % self.width();
             let y = u / self.width();
             let mut count: u32 = 0;
-            // We're working with unsigned numbers, so instead of the top left offset
-            // being -1,-1, it's 0,0. Also, to avoid underflowing, leave out the top
-            // (/left) row (/column) of cells if we're dealing with the topmost row
-            // (/leftmost column) on the board.
-            let min_x_offset = if x > 0 { 0 } else { 1 };
-            let min_y_offset = if y > 0 { 0 } else { 1 };
-            for i in min_x_offset..=2 {
-                for j in min_y_offset..=2 {
-                    if !(i == 1 && j == 1) {
-                        // subtract 1 to correct for 0,0 offset being top left
-                        // neighbor
-                        let peek_x = x + i - 1;
-                        let peek_y = y + j - 1;
-                        if let Some(true) = self.is_cell_alive(peek_x, peek_y) {
-                            count += 1;
+            // Look at the cells from one up and left of this one to one down and right. Those can
+            // be off the edge of the board, so use signed numbers for them, and let the topology
+            // work out which cell (if any) they wrap around to.
+            for i in -1..=1 {
+                for j in -1..=1 {
+                    if !(i == 0 && j == 0) {
+                        let peek = self.topology.wrap(
+                            x as isize + i,
+                            y as isize + j,
+                            self.width(),
+                            self.height(),
+                        );
+                        if let Some((peek_x, peek_y)) = peek {
+                            if let Some(true) = self.is_cell_alive(peek_x, peek_y) {
+                                count += 1;
+                            }
                         }
                     }
                 }
             }
 
-            // prevent clippy from complaining about not using a range check on the next if condition
-            #[allow(clippy::manual_range_contains)]
-            if count < 2 || count > 3 {
-                // Any live cell with fewer than two live neighbors dies, as if by under population.
-                // &
-                // Any live cell with more than three live neighbors dies, as if by overpopulation.
-                *square = false;
-            } else if *square && (count == 2 || count == 3) {
-                // Any live cell with two or three live neighbors lives on to the next generation.
-                // (*square is already true so nothing to do here)
-            } else if !*square && count == 2 {
-                // Any dead cell with exactly three live neighbors becomes a live cell, as if by reproduction.
-                *square = true;
-            }
+            // Any live cell with one of the rule's survival counts of live neighbors lives on to
+            // the next generation (for Conway's Game of Life, two or three), and any dead cell
+            // with one of the rule's birth counts becomes a live cell, as if by reproduction (for
+            // Conway's Game of Life, exactly three). Everything else dies or stays dead, as if
+            // by under population or overpopulation.
+            *square = self.rule.next_state(*square, count);
         }
         self.playground = new_playground;
     }
@@ -188,4 +200,90 @@ impl GameOfLife for GameOfLifeSolution {
     fn height(&self) -> usize {
         self.playground.len() / self.width
     }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        if let Some(square) = self.get_cell_mut(x, y) {
+            *square = alive;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.playground
+            .iter_mut()
+            .for_each(|square| *square = false);
+    }
+
+    fn population(&self) -> usize {
+        self.playground.iter().filter(|&&square| square).count()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        Box::new(
+            self.playground
+                .iter()
+                .enumerate()
+                .filter(|&(_, &square)| square)
+                .map(move |(u, _)| ((u % self.width) as i64, (u / self.width) as i64)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod solution_test {
+    use super::{GameOfLife, GameOfLifeSolution};
+    use crate::rule::Rule;
+    use crate::test_support::live_cells;
+    use crate::topology::Topology;
+
+    #[test]
+    fn blinker_oscillates() {
+        let mut game = GameOfLifeSolution::new(5, 5);
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(2, 1), (2, 2), (2, 3)]);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn two_neighbours_are_not_enough_for_a_birth() {
+        let mut game = GameOfLifeSolution::new(3, 3);
+        game.toggle_cell(0, 0);
+        game.toggle_cell(2, 0);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![]);
+    }
+
+    #[test]
+    fn follows_the_given_rule() {
+        // under Seeds (B2/S) both live cells die, but the column of cells between them is born
+        let mut game = GameOfLifeSolution::new(3, 3).with_rule("B2/S".parse().unwrap());
+        game.toggle_cell(0, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![(1, 0), (1, 1), (1, 2)]);
+        // whereas under Conway's rule they'd just die
+        let mut game = GameOfLifeSolution::new(3, 3).with_rule(Rule::conway());
+        game.toggle_cell(0, 1);
+        game.toggle_cell(2, 1);
+        game.tick();
+        assert_eq!(live_cells(&game), vec![]);
+    }
+
+    #[test]
+    fn gliders_wrap_around_a_torus() {
+        let mut game = GameOfLifeSolution::new(6, 6).with_topology(Topology::Torus);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        let start = live_cells(&game);
+        // a glider moves one cell diagonally every 4 ticks, so after going all the way around
+        // the board it's back where it started
+        for _ in 0..4 * 6 {
+            game.tick();
+        }
+        assert_eq!(live_cells(&game), start);
+    }
 }
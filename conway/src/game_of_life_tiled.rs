@@ -0,0 +1,282 @@
+use crate::game_of_life::GameOfLife;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use std::mem;
+
+/// An implementation of GameOfLife that skips the parts of the board that can't change.
+///
+/// The board is divided into square tiles, and each tick only the tiles that changed last tick
+/// (and the tiles next to them) are worked out again. Every other tile, and everything around
+/// it, looked the same last tick as it does now, so it's going to look the same next tick too.
+/// On boards that are mostly empty or still lifes, that's most of them.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeTiled {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+    // the board as it was a tick ago, whose tiles are reused for the ones that don't change;
+    // tiles that haven't changed are the same here as in `cells`
+    previous: Vec<bool>,
+    tiles_across: usize,
+    tiles_down: usize,
+    // which tiles changed in the last tick (or have had cells toggled since)
+    changed: Vec<bool>,
+    // how many tiles were worked out in the last tick
+    active_tiles: usize,
+    rule: Rule,
+    topology: Topology,
+}
+
+impl GameOfLifeTiled {
+    /// How many cells wide and high each tile is.
+    pub const TILE_SIZE: usize = 16;
+
+    pub fn new(width: usize, height: usize) -> GameOfLifeTiled {
+        assert!(width > 0, "game width must be greater than 0");
+        assert!(height > 0, "game height must be greater than 0");
+        let tiles_across = width.div_ceil(GameOfLifeTiled::TILE_SIZE);
+        let tiles_down = height.div_ceil(GameOfLifeTiled::TILE_SIZE);
+        GameOfLifeTiled {
+            width,
+            height,
+            cells: vec![false; width * height],
+            previous: vec![false; width * height],
+            tiles_across,
+            tiles_down,
+            // work everything out the first time around
+            changed: vec![true; tiles_across * tiles_down],
+            active_tiles: 0,
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+        }
+    }
+
+    /// Run the game by the given two state, totalistic rule instead of Conway's `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeTiled {
+        rule.assert_two_state_totalistic("GameOfLifeTiled");
+        GameOfLifeTiled {
+            rule,
+            changed: vec![true; self.changed.len()],
+            ..self
+        }
+    }
+
+    /// Join up the edges of the board as the given topology says, rather than leaving them dead.
+    pub fn with_topology(self, topology: Topology) -> GameOfLifeTiled {
+        GameOfLifeTiled {
+            topology,
+            changed: vec![true; self.changed.len()],
+            ..self
+        }
+    }
+
+    /// How many tiles were worked out in the last tick.
+    pub fn active_tiles(&self) -> usize {
+        self.active_tiles
+    }
+
+    /// How many tiles the board is divided into.
+    pub fn tile_count(&self) -> usize {
+        self.changed.len()
+    }
+
+    /// Work out which tiles need working out this tick: the ones that changed, and the ones next
+    /// to those.
+    fn tiles_to_update(&self) -> Vec<bool> {
+        let mut update = vec![false; self.changed.len()];
+        let (across, down) = (self.tiles_across, self.tiles_down);
+        let mut touches_edge = false;
+        for tile_y in 0..down {
+            for tile_x in 0..across {
+                if !self.changed[tile_x + tile_y * across] {
+                    continue;
+                }
+                touches_edge |=
+                    tile_x == 0 || tile_y == 0 || tile_x == across - 1 || tile_y == down - 1;
+                for y in tile_y.saturating_sub(1)..=(tile_y + 1).min(down - 1) {
+                    for x in tile_x.saturating_sub(1)..=(tile_x + 1).min(across - 1) {
+                        update[x + y * across] = true;
+                    }
+                }
+            }
+        }
+        // when the edges are joined up, a change by one edge can affect tiles all around the
+        // others, so just update every tile along the edges
+        if touches_edge && self.topology != Topology::Plane {
+            for y in 0..down {
+                for x in 0..across {
+                    if x == 0 || y == 0 || x == across - 1 || y == down - 1 {
+                        update[x + y * across] = true;
+                    }
+                }
+            }
+        }
+        update
+    }
+
+    fn live_neighbours(&self, x: usize, y: usize) -> u32 {
+        let mut count = 0;
+        let inside = x > 0 && y > 0 && x + 1 < self.width && y + 1 < self.height;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                // cells away from the edges can skip asking the topology
+                let neighbour = if inside {
+                    Some((nx as usize, ny as usize))
+                } else {
+                    self.topology.wrap(nx, ny, self.width, self.height)
+                };
+                if let Some((nx, ny)) = neighbour {
+                    count += self.cells[nx + ny * self.width] as u32;
+                }
+            }
+        }
+        count
+    }
+}
+
+impl GameOfLife for GameOfLifeTiled {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        if x < self.width && y < self.height {
+            Some(self.cells[x + y * self.width])
+        } else {
+            None
+        }
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] ^= true;
+            let tile =
+                x / GameOfLifeTiled::TILE_SIZE + y / GameOfLifeTiled::TILE_SIZE * self.tiles_across;
+            self.changed[tile] = true;
+        }
+    }
+
+    fn tick(&mut self) {
+        let update = self.tiles_to_update();
+        // tiles that aren't updated are the same in `previous` as they are now, and they're not
+        // going to change, so `previous` can be reused for the next state of the board
+        let mut next = mem::take(&mut self.previous);
+        let mut changed = vec![false; self.changed.len()];
+        let size = GameOfLifeTiled::TILE_SIZE;
+        for (tile, _) in update.iter().enumerate().filter(|&(_, &update)| update) {
+            let (left, top) = (
+                (tile % self.tiles_across) * size,
+                (tile / self.tiles_across) * size,
+            );
+            for y in top..(top + size).min(self.height) {
+                for x in left..(left + size).min(self.width) {
+                    let i = x + y * self.width;
+                    next[i] = self
+                        .rule
+                        .next_state(self.cells[i], self.live_neighbours(x, y));
+                    changed[tile] |= next[i] != self.cells[i];
+                }
+            }
+        }
+        self.previous = mem::replace(&mut self.cells, next);
+        self.changed = changed;
+        self.active_tiles = update.iter().filter(|&&update| update).count();
+    }
+
+    /// Stops early once a tick changes nothing, since then nothing will ever change again.
+    fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+            if !self.changed.contains(&true) {
+                break;
+            }
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn tick_report(&self) -> Option<String> {
+        Some(format!(
+            "{} of {} tiles active",
+            self.active_tiles,
+            self.tile_count()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tiled_test {
+    use super::{GameOfLife, GameOfLifeTiled};
+    use crate::rule::Rule;
+    use crate::test_support::{assert_runs_like_the_solution, live_cells};
+    use crate::topology::{Edges, Topology};
+
+    #[test]
+    fn only_updates_tiles_near_changes() {
+        // a 4x4 grid of tiles, with a blinker in the top left tile and a block in the bottom
+        // right one
+        let mut game = GameOfLifeTiled::new(64, 64);
+        for x in 2..5 {
+            game.toggle_cell(x, 3);
+        }
+        for &(x, y) in &[(60, 60), (61, 60), (60, 61), (61, 61)] {
+            game.toggle_cell(x, y);
+        }
+        game.tick();
+        assert_eq!(game.active_tiles(), 16);
+        // after that, only the blinker's tile keeps changing, so just it and its neighbours are
+        // updated
+        game.tick();
+        assert_eq!(game.active_tiles(), 4);
+        assert_eq!(
+            live_cells(&game),
+            vec![
+                (2, 3),
+                (3, 3),
+                (4, 3),
+                (60, 60),
+                (61, 60),
+                (60, 61),
+                (61, 61)
+            ]
+        );
+        assert_eq!(game.tick_report().as_deref(), Some("4 of 16 tiles active"));
+    }
+
+    #[test]
+    fn runs_like_the_solution() {
+        // an awkward size, so the tiles at the right and bottom are cut short
+        let (width, height) = (50, 37);
+        let topologies = [
+            Topology::Plane,
+            Topology::Torus,
+            Topology::TwistedTorus {
+                edges: Edges::Horizontal,
+                shift: 7,
+            },
+        ];
+        for &rule in &["B3/S23", "B36/S23", "B0/S8"] {
+            for &topology in &topologies {
+                let rule: Rule = rule.parse().unwrap();
+                let mut tiled = GameOfLifeTiled::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology);
+                // a glider and an R-pentomino, which make plenty of changes over time, but leave
+                // most tiles alone
+                for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+                    tiled.toggle_cell(x + 30, y + 20);
+                }
+                for &(x, y) in &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+                    tiled.toggle_cell(x + 10, y + 12);
+                }
+                assert_runs_like_the_solution(&mut tiled, rule, topology, 150, Some(40), "tiled");
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::game_of_life::{BoundingBox, GameOfLife};
+use crate::rule::Rule;
+use std::collections::{HashMap, HashSet};
+
+/// An implementation of GameOfLife on a board that goes on forever in every direction, so that
+/// spaceships can fly off as far as they like.
+///
+/// Rather than storing every cell, it keeps a set of the coordinates of the live ones, which can
+/// be negative. Each tick, every live cell adds one to the count of each of its neighbours, and
+/// only cells that end up with a count can be alive next tick.
+///
+/// The width and height it's created with are just the size of the area that patterns are put in
+/// and that the UI starts out showing; cells outside that area work just as well.
+#[derive(Clone, Debug)]
+pub struct GameOfLifeUnbounded {
+    width: usize,
+    height: usize,
+    live_cells: HashSet<(i64, i64)>,
+    rule: Rule,
+}
+
+impl GameOfLifeUnbounded {
+    pub fn new(width: usize, height: usize) -> GameOfLifeUnbounded {
+        GameOfLifeUnbounded {
+            width,
+            height,
+            live_cells: HashSet::new(),
+            rule: Rule::conway(),
+        }
+    }
+
+    /// Run the game by a two state, totalistic rule without births on 0, instead of `B3/S23`.
+    pub fn with_rule(self, rule: Rule) -> GameOfLifeUnbounded {
+        rule.assert_two_state_totalistic("GameOfLifeUnbounded");
+        assert!(
+            !rule.is_born(0),
+            "{} has births on 0, which would fill the whole board",
+            rule
+        );
+        GameOfLifeUnbounded { rule, ..self }
+    }
+}
+
+impl GameOfLife for GameOfLifeUnbounded {
+    /// Cells are never out of bounds, so this always returns `Some`.
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.cell_state_at(x as i64, y as i64)
+            .map(|state| state == 1)
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        self.toggle_cell_at(x as i64, y as i64);
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.set_cell_at(x as i64, y as i64, alive);
+    }
+
+    fn tick(&mut self) {
+        // every live cell gets an entry even if it has no live neighbours, so that it gets the
+        // chance to survive
+        let mut counts: HashMap<(i64, i64), u32> =
+            self.live_cells.iter().map(|&cell| (cell, 0)).collect();
+        for &(x, y) in &self.live_cells {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if (dx, dy) != (0, 0) {
+                        *counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        self.live_cells = counts
+            .into_iter()
+            .filter(|(cell, count)| self.rule.next_state(self.live_cells.contains(cell), *count))
+            .map(|(cell, _)| cell)
+            .collect();
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn cell_state_at(&self, x: i64, y: i64) -> Option<u8> {
+        Some(self.live_cells.contains(&(x, y)) as u8)
+    }
+
+    fn toggle_cell_at(&mut self, x: i64, y: i64) {
+        if !self.live_cells.remove(&(x, y)) {
+            self.live_cells.insert((x, y));
+        }
+    }
+
+    fn set_cell_at(&mut self, x: i64, y: i64, alive: bool) {
+        if alive {
+            self.live_cells.insert((x, y));
+        } else {
+            self.live_cells.remove(&(x, y));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.live_cells.clear();
+    }
+
+    fn population(&self) -> usize {
+        self.live_cells.len()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        Box::new(self.live_cells.iter().copied())
+    }
+
+    fn live_bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::around(self.live_cells.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod unbounded_test {
+    use super::{GameOfLife, GameOfLifeUnbounded};
+    use crate::game_of_life::BoundingBox;
+    use crate::game_of_life_solution::GameOfLifeSolution;
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn gliders_fly_off_past_the_origin() {
+        let mut game = GameOfLifeUnbounded::new(10, 10);
+        // a glider heading up and to the left
+        for &(x, y) in &[(0, 0), (1, 0), (2, 0), (0, 1), (1, 2)] {
+            game.toggle_cell(x, y);
+        }
+        for _ in 0..4 * 10 {
+            game.tick();
+        }
+        // it's moved 10 cells diagonally, without running into anything
+        assert_eq!(
+            game.live_bounding_box(),
+            Some(BoundingBox {
+                x: -10,
+                y: -10,
+                width: 3,
+                height: 3,
+            })
+        );
+        assert_eq!(game.cell_state_at(-10, -10), Some(1));
+        assert_eq!(game.cell_state_at(-9, -8), Some(1));
+        assert_eq!(game.is_cell_alive(0, 0), Some(false));
+    }
+
+    #[test]
+    fn runs_like_the_solution_away_from_the_edges() {
+        let mut unbounded = GameOfLifeUnbounded::new(40, 40);
+        let mut solution = GameOfLifeSolution::new(40, 40);
+        // an R-pentomino, which doesn't reach the edges of the solution's board in 30 ticks
+        for &(x, y) in &[(20, 19), (21, 19), (19, 20), (20, 20), (20, 21)] {
+            unbounded.toggle_cell(x, y);
+            solution.toggle_cell(x, y);
+        }
+        for _ in 0..30 {
+            unbounded.tick();
+            solution.tick();
+        }
+        assert_eq!(
+            Pattern::from_game(&unbounded),
+            Pattern::from_game(&solution)
+        );
+        assert_eq!(unbounded.live_bounding_box(), solution.live_bounding_box());
+    }
+
+    #[test]
+    fn patterns_always_fit() {
+        let game = GameOfLifeUnbounded::new(2, 2);
+        let pattern = Pattern {
+            width: 5,
+            height: 5,
+            live_cells: vec![(0, 0), (4, 4)],
+            ..Pattern::default()
+        };
+        assert!(pattern.check_fits(&game, &Default::default()).is_ok());
+    }
+}
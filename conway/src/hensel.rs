@@ -0,0 +1,163 @@
+//! Hensel notation for "isotropic non-totalistic" rules, which care about which of a cell's
+//! neighbours are alive and not just how many of them are. For each number of live neighbours,
+//! the different arrangements of those neighbours (not counting ones that are just rotations or
+//! reflections of each other) are given a letter, so e.g. `2a` is two live neighbours that are
+//! next to each other, and `2i` is two live neighbours on opposite sides of the cell. See
+//! http://www.conwaylife.com/wiki/Isotropic_non-totalistic_rule for pictures of them all.
+//!
+//! Neighbourhoods are represented here as 8 bits, one per neighbour, going clockwise from the top:
+//! bit 0 is north, bit 1 north east, bit 2 east, and so on round to bit 7 for north west.
+
+/// The letters for each number of live neighbours, in the order Golly writes them.
+const LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "cekain",
+    "cekainyqjr",
+    "cekainyqjrtwz",
+    "cekainyqjr",
+    "cekain",
+    "ce",
+    "",
+];
+
+// compass directions as neighbourhood bits
+const N: u8 = 1;
+const NE: u8 = 1 << 1;
+const E: u8 = 1 << 2;
+const SE: u8 = 1 << 3;
+const S: u8 = 1 << 4;
+const SW: u8 = 1 << 5;
+const W: u8 = 1 << 6;
+const NW: u8 = 1 << 7;
+
+/// One arrangement of live neighbours for each letter (in the same order as `LETTERS`), for 1 to
+/// 4 live neighbours. Arrangements of 5 or more are the inverse of those with 8 minus as many,
+/// e.g. `5i` is all the neighbours that aren't in `3i`.
+const ARRANGEMENTS: [&[u8]; 5] = [
+    &[],
+    &[NE, N],
+    &[NE | SE, N | E, N | SE, N | NE, N | S, NE | SW],
+    &[
+        NE | SE | SW,
+        N | E | S,
+        N | E | SW,
+        N | NE | E,
+        N | NE | NW,
+        N | NE | SE,
+        N | SE | SW,
+        N | NE | SW,
+        N | NE | W,
+        N | NE | S,
+    ],
+    &[
+        NE | SE | SW | NW,
+        N | E | S | W,
+        N | NE | SE | W,
+        N | NE | E | SE,
+        N | NE | SE | S,
+        N | NE | SE | NW,
+        N | NE | SE | SW,
+        N | NE | E | SW,
+        N | NE | S | W,
+        N | NE | E | S,
+        N | NE | S | NW,
+        N | NE | SW | W,
+        N | NE | S | SW,
+    ],
+];
+
+/// The bits for every letter that's valid for the given number of live neighbours. Counts with no
+/// letters (0 and 8) have just the one arrangement, which is treated as a single letter.
+pub(crate) fn all_letters(count: usize) -> u16 {
+    match LETTERS[count].len() {
+        0 => 1,
+        letters => (1 << letters) - 1,
+    }
+}
+
+/// The bit for the given letter out of those for the given number of live neighbours, or `None`
+/// if that letter isn't valid for that many live neighbours.
+pub(crate) fn letter_bit(count: usize, letter: char) -> Option<u16> {
+    LETTERS[count].find(letter).map(|index| 1 << index)
+}
+
+/// The letters that the given bits stand for, for the given number of live neighbours.
+pub(crate) fn letters(count: usize, bits: u16) -> impl Iterator<Item = char> {
+    LETTERS[count]
+        .chars()
+        .enumerate()
+        .filter(move |&(index, _)| bits & 1 << index != 0)
+        .map(|(_, letter)| letter)
+}
+
+/// Work out how many live neighbours there are in the given neighbourhood, and the bit for the
+/// letter of their arrangement.
+pub(crate) fn classify(neighbours: u8) -> (usize, u16) {
+    let count = neighbours.count_ones() as usize;
+    if count == 0 || count == 8 {
+        return (count, 1);
+    }
+    // arrangements of more than 4 neighbours are classified by the neighbours that are dead
+    let (neighbours, arrangements) = if count > 4 {
+        (!neighbours, ARRANGEMENTS[8 - count])
+    } else {
+        (neighbours, ARRANGEMENTS[count])
+    };
+    let index = arrangements
+        .iter()
+        .position(|&arrangement| symmetries(arrangement).any(|n| n == neighbours))
+        .expect("every arrangement of neighbours has a letter");
+    (count, 1 << index)
+}
+
+/// Every rotation and reflection of the given neighbourhood.
+fn symmetries(neighbours: u8) -> impl Iterator<Item = u8> {
+    // mirroring left to right swaps east and west, but leaves north and south alone
+    let mirrored = (0..8)
+        .filter(|bit| neighbours & 1 << bit != 0)
+        .fold(0, |mirrored, bit| mirrored | 1 << ((8 - bit) % 8));
+    // each quarter turn moves every neighbour two places round the cell
+    IntoIterator::into_iter([neighbours, mirrored])
+        .flat_map(|n| (0..4).map(move |turns| n.rotate_left(2 * turns)))
+}
+
+#[cfg(test)]
+mod hensel_test {
+    use super::{all_letters, classify, LETTERS, N, NW, S, SE, SW, W};
+
+    #[test]
+    fn every_letter_is_a_different_arrangement() {
+        // each of the 256 neighbourhoods should have exactly one letter, and every letter should
+        // be used by some neighbourhood
+        let mut used = [0u16; 9];
+        for neighbours in 0..=255 {
+            let (count, bit) = classify(neighbours);
+            assert_eq!(count, neighbours.count_ones() as usize);
+            used[count] |= bit;
+        }
+        for count in 0..=8 {
+            assert_eq!(
+                used[count],
+                all_letters(count),
+                "letters {}",
+                LETTERS[count]
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_rotations_and_reflections_the_same() {
+        let letter_of = |neighbours| {
+            let (count, bit) = classify(neighbours);
+            let index = bit.trailing_zeros() as usize;
+            format!("{}{}", count, &LETTERS[count][index..=index])
+        };
+        assert_eq!(letter_of(N | S), "2i");
+        assert_eq!(letter_of(W | NW), "2a");
+        assert_eq!(letter_of(NW | SE), "2n");
+        assert_eq!(letter_of(SW | W | NW), "3i");
+        // 5i is everything but a 3i
+        assert_eq!(letter_of(!(SW | S | SE)), "5i");
+    }
+}
@@ -0,0 +1,299 @@
+use crate::rule::{Rule, RuleParseError};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// A "Larger than Life" rule, where cells look further than just their immediate neighbours: each
+/// cell counts the live cells within some range of it, and is born or survives if that count is
+/// within a given range.
+///
+/// These are written the way Golly does, e.g. Bosco's Rule is `R5,C0,M1,S34..58,B34..45,NM`:
+///
+/// * `R5` - cells look 5 cells away in each direction
+/// * `C0` - how many states cells can be in; 0 or 2 means just dead and alive, and more than that
+///   means dying states, as with Generations rules (see `Rule`)
+/// * `M1` - the cell itself is counted along with its neighbours (`M0` means it isn't)
+/// * `S34..58` - live cells with 34 to 58 live cells around them survive
+/// * `B34..45` - dead cells with 34 to 45 live cells around them are born
+/// * `NM` - the shape of the neighbourhood; see `Neighbourhood`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LargerThanLifeRule {
+    range: usize,
+    states: u8,
+    include_middle: bool,
+    survival: RangeInclusive<usize>,
+    birth: RangeInclusive<usize>,
+    neighbourhood: Neighbourhood,
+}
+
+/// Which cells within range of a cell count as its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    /// The square of cells within range both horizontally and vertically (`NM`).
+    Moore,
+    /// The diamond of cells within range when moving only horizontally and vertically (`NN`).
+    VonNeumann,
+    /// The disc of cells whose centres are within range in a straight line (`NC`).
+    Circular,
+}
+
+impl LargerThanLifeRule {
+    /// The largest range supported, which is as far as Golly goes too.
+    pub const MAX_RANGE: usize = 500;
+
+    /// How many cells away from a cell its neighbours can be.
+    pub fn range(&self) -> usize {
+        self.range
+    }
+
+    /// How many states cells can be in: 2 (dead or alive), or more if cells take a while to die.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Is a cell counted as one of its own neighbours?
+    pub fn includes_middle(&self) -> bool {
+        self.include_middle
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    /// For each row of the neighbourhood, from `range` rows above the cell down to `range` rows
+    /// below it, how far to the left and right of the cell the neighbourhood goes on that row.
+    pub fn row_extents(&self) -> Vec<usize> {
+        let range = self.range as isize;
+        (-range..=range)
+            .map(|dy| {
+                let dy = dy.unsigned_abs();
+                match self.neighbourhood {
+                    Neighbourhood::Moore => self.range,
+                    Neighbourhood::VonNeumann => self.range - dy,
+                    Neighbourhood::Circular => {
+                        // the widest dx such that dx^2 + dy^2 <= range^2
+                        let limit = self.range * self.range - dy * dy;
+                        (0..=self.range)
+                            .take_while(|dx| dx * dx <= limit)
+                            .last()
+                            .unwrap()
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// What state a cell in the given state (0 for dead, 1 for alive, and 2 and up for dying)
+    /// is in next tick, given how many live cells there are in its neighbourhood (which includes
+    /// the cell itself if the rule says so).
+    pub fn next_state(&self, state: u8, live_neighbours: usize) -> u8 {
+        match state {
+            0 if self.birth.contains(&live_neighbours) => 1,
+            0 => 0,
+            1 if self.survival.contains(&live_neighbours) => 1,
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for LargerThanLifeRule {
+    /// Conway's Game of Life, written as a Larger than Life rule.
+    fn default() -> LargerThanLifeRule {
+        LargerThanLifeRule {
+            range: 1,
+            states: 2,
+            include_middle: false,
+            survival: 2..=3,
+            birth: 3..=3,
+            neighbourhood: Neighbourhood::Moore,
+        }
+    }
+}
+
+impl fmt::Display for LargerThanLifeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.range,
+            // Golly writes two states as `C0`
+            if self.states == 2 { 0 } else { self.states },
+            self.include_middle as u8,
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+            match self.neighbourhood {
+                Neighbourhood::Moore => 'M',
+                Neighbourhood::VonNeumann => 'N',
+                Neighbourhood::Circular => 'C',
+            }
+        )
+    }
+}
+
+impl FromStr for LargerThanLifeRule {
+    type Err = RuleParseError;
+
+    /// Parse a rule such as `R5,C0,M1,S34..58,B34..45,NM`, in any case. The `C`, `M` and `N`
+    /// parts can be left out, in which case they're `C0`, `M0` and `NM`.
+    ///
+    /// Ordinary rules such as `B3/S23` (see `Rule`) are accepted too, as long as the counts cells
+    /// are born and survive on are each one unbroken range, since they're the same as a range 1
+    /// Larger than Life rule, e.g. `R1,C0,M0,S2..3,B3..3,NM`.
+    fn from_str(s: &str) -> Result<LargerThanLifeRule, RuleParseError> {
+        let error = |reason: &str| {
+            RuleParseError(format!(
+                "{:?} is not a valid Larger than Life rule: {}",
+                s, reason
+            ))
+        };
+        if s.contains('/') {
+            let rule: Rule = s.parse()?;
+            if !rule.is_totalistic() {
+                return Err(error("it isn't totalistic"));
+            }
+            let counts = |matches: &dyn Fn(u32) -> bool, which: &str| {
+                let counts: Vec<usize> = (0..=8)
+                    .filter(|&n| matches(n))
+                    .map(|n| n as usize)
+                    .collect();
+                match (counts.first(), counts.last()) {
+                    (Some(&min), Some(&max)) if max - min + 1 == counts.len() => Ok(min..=max),
+                    _ => Err(error(&format!(
+                        "the counts cells {} on must be one unbroken range",
+                        which
+                    ))),
+                }
+            };
+            return Ok(LargerThanLifeRule {
+                states: rule.states(),
+                survival: counts(&|n| rule.survives(n), "survive")?,
+                birth: counts(&|n| rule.is_born(n), "are born")?,
+                ..LargerThanLifeRule::default()
+            });
+        }
+        let number = |text: &str| {
+            text.parse::<usize>()
+                .map_err(|_| error(&format!("{:?} is not a number", text)))
+        };
+        let count_range = |text: &str| {
+            let (min, max) = text
+                .split_once("..")
+                .ok_or_else(|| error("expected a range of counts such as 34..45"))?;
+            Ok::<_, RuleParseError>(number(min)?..=number(max)?)
+        };
+
+        let mut rule = LargerThanLifeRule::default();
+        let (mut range, mut survival, mut birth) = (None, None, None);
+        for part in s.trim().split(',').map(str::trim) {
+            let letter = part.chars().next().map(|c| c.to_ascii_uppercase());
+            let value = part.get(1..).unwrap_or("");
+            match letter {
+                Some('R') => range = Some(number(value)?),
+                Some('C') => {
+                    rule.states = match number(value)? {
+                        0 | 2 => 2,
+                        states @ 3..=255 => states as u8,
+                        _ => return Err(error("the number of states must be 0 or 2 to 255")),
+                    }
+                }
+                Some('M') => {
+                    rule.include_middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err(error("M must be 0 or 1")),
+                    }
+                }
+                Some('S') => survival = Some(count_range(value)?),
+                Some('B') => birth = Some(count_range(value)?),
+                Some('N') => {
+                    rule.neighbourhood = match value.to_ascii_uppercase().as_str() {
+                        "M" => Neighbourhood::Moore,
+                        "N" => Neighbourhood::VonNeumann,
+                        "C" => Neighbourhood::Circular,
+                        _ => return Err(error("the neighbourhood must be NM, NN or NC")),
+                    }
+                }
+                _ => return Err(error(&format!("unexpected part {:?}", part))),
+            }
+        }
+
+        rule.range = range.ok_or_else(|| error("missing the range (R)"))?;
+        if rule.range < 1 || rule.range > LargerThanLifeRule::MAX_RANGE {
+            return Err(error(&format!(
+                "the range must be between 1 and {}",
+                LargerThanLifeRule::MAX_RANGE
+            )));
+        }
+        rule.survival = survival.ok_or_else(|| error("missing the survival counts (S)"))?;
+        rule.birth = birth.ok_or_else(|| error("missing the birth counts (B)"))?;
+        Ok(rule)
+    }
+}
+
+#[cfg(test)]
+mod larger_than_life_test {
+    use super::{LargerThanLifeRule, Neighbourhood};
+
+    #[test]
+    fn parses_and_displays_golly_notation() {
+        let bosco: LargerThanLifeRule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+        assert_eq!(bosco.range(), 5);
+        assert_eq!(bosco.states(), 2);
+        assert!(bosco.includes_middle());
+        assert_eq!(bosco.neighbourhood(), Neighbourhood::Moore);
+        assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+
+        let lower: LargerThanLifeRule = "r2,c3,s1..2,b2..4,nn".parse().unwrap();
+        assert_eq!(lower.to_string(), "R2,C3,M0,S1..2,B2..4,NN");
+    }
+
+    #[test]
+    fn accepts_ordinary_rules_with_unbroken_ranges() {
+        let conway: LargerThanLifeRule = "B3/S23".parse().unwrap();
+        assert_eq!(conway, LargerThanLifeRule::default());
+        assert_eq!(conway.to_string(), "R1,C0,M0,S2..3,B3..3,NM");
+        let star_wars: LargerThanLifeRule = "B2/S345/C4".parse().unwrap();
+        assert_eq!(star_wars.to_string(), "R1,C4,M0,S3..5,B2..2,NM");
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for rule in &[
+            "",
+            "B3",
+            // HighLife is born on 3 and 6 but not 4 or 5, and Brian's Brain never survives
+            "B36/S23",
+            "B2/S/C3",
+            "B2-a/S12",
+            "R0,S1..2,B3..3",
+            "R501,S1..2,B3..3",
+            "R2,S1..2",
+            "R2,S1,B3..3",
+            "R2,S1..2,B3..3,NX",
+            "R2,M2,S1..2,B3..3",
+        ] {
+            assert!(rule.parse::<LargerThanLifeRule>().is_err(), "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn neighbourhood_shapes() {
+        let rule = |neighbourhood| LargerThanLifeRule {
+            range: 3,
+            neighbourhood,
+            ..LargerThanLifeRule::default()
+        };
+        assert_eq!(rule(Neighbourhood::Moore).row_extents(), vec![3; 7]);
+        assert_eq!(
+            rule(Neighbourhood::VonNeumann).row_extents(),
+            vec![0, 1, 2, 3, 2, 1, 0]
+        );
+        assert_eq!(
+            rule(Neighbourhood::Circular).row_extents(),
+            vec![0, 2, 2, 3, 2, 2, 0]
+        );
+    }
+}
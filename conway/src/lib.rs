@@ -1,25 +1,100 @@
+use std::io;
 use std::path::Path;
 
 // declare what modules this library provides
+mod analysis;
+mod error;
+mod format;
 mod game_of_life;
+mod game_of_life_bit_packed;
 mod game_of_life_broken;
+mod game_of_life_generations;
+mod game_of_life_hashlife;
+mod game_of_life_incremental;
+mod game_of_life_isotropic;
+mod game_of_life_larger_than_life;
 mod game_of_life_mine;
+mod game_of_life_parallel;
+#[cfg(feature = "simd")]
+mod game_of_life_simd;
 mod game_of_life_solution;
+mod game_of_life_tiled;
+mod game_of_life_unbounded;
+mod hensel;
+mod larger_than_life;
+mod lif;
+mod macrocell;
+mod pattern;
+mod placement;
+mod plaintext;
+mod rle;
+mod rule;
+mod stats;
+#[cfg(test)]
+mod test_support;
+mod topology;
 
 // export bits of relevant modules to avoid consuming crates depending on internal implementation
 // details
-pub use game_of_life::GameOfLife;
+pub use analysis::{
+    find_period, find_spaceship, find_stabilisation, Direction, PeriodDetector, Periodicity,
+    Spaceship, SpaceshipDetector, Stabilisation, StabilityDetector, MAX_OBSERVED_POPULATION,
+};
+pub use error::{ParseError, ParseErrorKind, PatternError};
+pub use format::PatternFormat;
+pub use game_of_life::{BoundingBox, GameOfLife};
+pub use game_of_life_bit_packed::GameOfLifeBitPacked;
 pub use game_of_life_broken::GameOfLiveBroken;
+pub use game_of_life_generations::GameOfLifeGenerations;
+pub use game_of_life_hashlife::GameOfLifeHashLife;
+pub use game_of_life_incremental::GameOfLifeIncremental;
+pub use game_of_life_isotropic::GameOfLifeIsotropic;
+pub use game_of_life_larger_than_life::GameOfLifeLargerThanLife;
 pub use game_of_life_mine::GameOfLifeMine;
+pub use game_of_life_parallel::GameOfLifeParallel;
+#[cfg(feature = "simd")]
+pub use game_of_life_simd::{GameOfLifeSimd, InstructionSet};
 pub use game_of_life_solution::GameOfLifeSolution;
+pub use game_of_life_tiled::GameOfLifeTiled;
+pub use game_of_life_unbounded::GameOfLifeUnbounded;
+pub use larger_than_life::{LargerThanLifeRule, Neighbourhood};
+pub use pattern::Pattern;
+pub use placement::{Flip, Placement, PlacementParseError, Position, Rotation};
+pub use rule::{Rule, RuleParseError, RuleTable};
+pub use stats::{Stats, WithStats};
+pub use topology::{BoundedGrid, Edges, Topology};
 
-#[allow(unused_variables)] //TODO remove this lint once you're implementing this function for part 2
-pub fn load_and_apply_pattern(game: &mut dyn game_of_life::GameOfLife, pattern_filename: &Path) {
-    //TODO fill this out for the part 2 exercise :)
-    unimplemented!(
-        "Need to implement loading pattern from file {:?}",
-        &pattern_filename
-    );
+/// Loads the pattern in the given file into the given game, with the top left of the pattern at
+/// the top left of the board.
+///
+/// The file can be in any of the formats in `PatternFormat`; which one is worked out from the
+/// file's contents and extension. Any part of the pattern that doesn't fit on the board is left
+/// off.
+pub fn load_and_apply_pattern(
+    game: &mut dyn game_of_life::GameOfLife,
+    pattern_filename: &Path,
+) -> Result<(), PatternError> {
+    Pattern::load(pattern_filename)?.apply(game);
+    Ok(())
+}
+
+/// Encodes the live cells of the given game as the contents of a pattern file in the given format.
+///
+/// The pattern is cropped to the smallest box containing every live cell, so the empty space
+/// around it is not kept.
+pub fn encode_pattern(game: &dyn game_of_life::GameOfLife, format: PatternFormat) -> String {
+    Pattern::from_game(game).encode(format)
+}
+
+/// Saves the live cells of the given game to a pattern file, which can then be loaded again with
+/// `load_and_apply_pattern`.
+///
+/// The format is picked based on the file extension, falling back to RLE for unknown extensions.
+pub fn save_pattern(
+    game: &dyn game_of_life::GameOfLife,
+    pattern_filename: &Path,
+) -> io::Result<()> {
+    Pattern::from_game(game).save(pattern_filename)
 }
 
 /// Loads a nice default pattern into the given game
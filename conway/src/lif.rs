@@ -0,0 +1,255 @@
+//! Reading and writing of the Life 1.05 and Life 1.06 pattern formats (usually with a `.lif`
+//! extension).
+//!
+//! Life 1.06 is just a list of `x y` coordinates of live cells, one per line. Life 1.05 instead
+//! draws the pattern out in blocks of `.` and `*` characters, each positioned by a preceding
+//! `#P x y` line. See http://www.conwaylife.com/wiki/Life_1.06 and
+//! http://www.conwaylife.com/wiki/Life_1.05 for the details.
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::pattern::Pattern;
+
+pub(crate) const LIFE_105_HEADER: &str = "#Life 1.05";
+pub(crate) const LIFE_106_HEADER: &str = "#Life 1.06";
+
+/// Parse the contents of a Life 1.06 file.
+pub(crate) fn parse_106(contents: &str) -> Result<Pattern, ParseError> {
+    let mut live_cells = Vec::new();
+    for (line_no, line) in (1..).zip(contents.lines()) {
+        // the `#Life 1.06` header is itself a comment, which keeps things simple
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let coordinates = parse_numbers(line_no, 1, line)?;
+        match coordinates[..] {
+            [(_, x), (_, y)] => live_cells.push((x, y)),
+            _ => {
+                return Err(ParseError::new(
+                    line_no,
+                    1,
+                    ParseErrorKind::InvalidNumber("expected an `x y` pair".to_owned()),
+                ))
+            }
+        }
+    }
+    Ok(Pattern::from_coordinates(live_cells))
+}
+
+/// Parse the contents of a Life 1.05 file.
+pub(crate) fn parse_105(contents: &str) -> Result<Pattern, ParseError> {
+    let mut live_cells = Vec::new();
+    let mut rule = None;
+    let mut comments = Vec::new();
+    // rows of cells are drawn relative to the position given by the most recent `#P` line
+    let (mut block_x, mut block_y) = (0, 0);
+    let mut row = 0;
+    for (line_no, line) in (1..).zip(contents.lines()) {
+        let line = line.trim_end();
+        if let Some(position) = line.strip_prefix("#P") {
+            match parse_numbers(line_no, 3, position)?[..] {
+                [(_, x), (_, y)] => {
+                    block_x = x;
+                    block_y = y;
+                    row = 0;
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        line_no,
+                        1,
+                        ParseErrorKind::InvalidNumber("expected `#P x y`".to_owned()),
+                    ))
+                }
+            }
+        } else if let Some(rule_text) = line.strip_prefix("#R") {
+            // Life 1.05 writes rules in survival/birth order, e.g. `23/3` for Conway's Life
+            rule = Some(rule_text.trim().to_owned());
+        } else if line.starts_with("#N") {
+            rule = Some("23/3".to_owned());
+        } else if let Some(description) = line.strip_prefix("#D") {
+            comments.push(
+                description
+                    .strip_prefix(' ')
+                    .unwrap_or(description)
+                    .to_owned(),
+            );
+        } else if line.starts_with('#') {
+            // any other `#` line is the header or something we don't understand, so skip it
+        } else {
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    '*' => live_cells.push((block_x + x as i64, block_y + row)),
+                    c => {
+                        return Err(ParseError::new(
+                            line_no,
+                            x + 1,
+                            ParseErrorKind::UnexpectedCharacter(c),
+                        ))
+                    }
+                }
+            }
+            row += 1;
+        }
+    }
+    Ok(Pattern {
+        rule,
+        comments,
+        ..Pattern::from_coordinates(live_cells)
+    })
+}
+
+/// Split some text into whitespace separated integers, along with the column each one starts at
+/// (given that the text itself starts at `first_column`).
+fn parse_numbers(
+    line_no: usize,
+    first_column: usize,
+    text: &str,
+) -> Result<Vec<(usize, i64)>, ParseError> {
+    let mut numbers = Vec::new();
+    let mut chars = (first_column..).zip(text.chars()).peekable();
+    while let Some(&(column, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            token.push(c);
+            chars.next();
+        }
+        let number = token.parse::<i64>().map_err(|_| {
+            ParseError::new(
+                line_no,
+                column,
+                ParseErrorKind::InvalidNumber(format!("{:?} is not a whole number", token)),
+            )
+        })?;
+        numbers.push((column, number));
+    }
+    Ok(numbers)
+}
+
+/// Encode the given pattern as the contents of a Life 1.06 file. The format has no room for
+/// anything but cells, so the pattern's name, comments and so on are left out.
+pub(crate) fn write_106(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", LIFE_106_HEADER);
+    let (origin_x, origin_y) = pattern.origin.unwrap_or((0, 0));
+    for &(x, y) in &pattern.live_cells {
+        out.push_str(&format!(
+            "{} {}\n",
+            origin_x + x as i64,
+            origin_y + y as i64
+        ));
+    }
+    out
+}
+
+/// Encode the given pattern as the contents of a Life 1.05 file, as a single block of cells.
+pub(crate) fn write_105(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", LIFE_105_HEADER);
+    // descriptions are the only kind of metadata the format has, so fold the name & author in
+    let descriptions = pattern
+        .name
+        .iter()
+        .chain(&pattern.author)
+        .chain(&pattern.comments);
+    for description in descriptions {
+        out.push_str(&format!("#D {}\n", description));
+    }
+    match &pattern.rule {
+        Some(rule) => out.push_str(&format!("#R {}\n", rule)),
+        None => out.push_str("#N\n"),
+    }
+    let (origin_x, origin_y) = pattern.origin.unwrap_or((0, 0));
+    out.push_str(&format!("#P {} {}\n", origin_x, origin_y));
+    for row in pattern.draw_rows('.', '*') {
+        // empty rows still need a character, or they'd read as a blank line
+        out.push_str(if row.is_empty() { "." } else { &row });
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod lif_test {
+    use super::{parse_105, parse_106, write_105, write_106};
+    use crate::error::{ParseError, ParseErrorKind};
+
+    #[test]
+    fn parses_life_106_with_negative_coordinates() {
+        let pattern = parse_106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(
+            pattern.live_cells,
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn reports_position_of_bad_life_106_coordinate() {
+        assert_eq!(
+            parse_106("#Life 1.06\n0 -1\n1  zero\n").unwrap_err(),
+            ParseError::new(
+                3,
+                4,
+                ParseErrorKind::InvalidNumber("\"zero\" is not a whole number".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn parses_life_105_blocks() {
+        let contents = "#Life 1.05\n#D Two blocks\n#N\n#P -1 -1\n**\n**\n#P 3 -1\n**\n**\n";
+        let pattern = parse_105(contents).unwrap();
+        assert_eq!((pattern.width, pattern.height), (6, 2));
+        assert_eq!(pattern.rule.as_deref(), Some("23/3"));
+        assert_eq!(pattern.comments, vec!["Two blocks"]);
+        assert_eq!(pattern.origin, Some((-1, -1)));
+        assert_eq!(
+            pattern.live_cells,
+            vec![
+                (0, 0),
+                (1, 0),
+                (0, 1),
+                (1, 1),
+                (4, 0),
+                (5, 0),
+                (4, 1),
+                (5, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_position_of_unexpected_life_105_character() {
+        assert_eq!(
+            parse_105("#Life 1.05\n#P 0 0\n.*\n.O\n").unwrap_err(),
+            ParseError::new(4, 2, ParseErrorKind::UnexpectedCharacter('O'))
+        );
+    }
+
+    #[test]
+    fn reports_position_of_bad_life_105_block_position() {
+        assert_eq!(
+            parse_105("#Life 1.05\n#P 0 x\n**\n").unwrap_err(),
+            ParseError::new(
+                2,
+                6,
+                ParseErrorKind::InvalidNumber("\"x\" is not a whole number".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn round_trips_through_writers() {
+        let pattern = parse_106("#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n").unwrap();
+        assert_eq!(parse_106(&write_106(&pattern)).unwrap(), pattern);
+
+        let reloaded = parse_105(&write_105(&pattern)).unwrap();
+        assert_eq!(reloaded.live_cells, pattern.live_cells);
+        assert_eq!(reloaded.origin, pattern.origin);
+    }
+}
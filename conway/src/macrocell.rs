@@ -0,0 +1,531 @@
+//! Reading and writing of Golly's Macrocell pattern format (usually with a `.mc` extension).
+//!
+//! Rather than listing cells, a Macrocell file describes a quadtree: each line is a node, which is
+//! either an 8x8 leaf drawn out with `.`, `*` and `$` (like a tiny RLE file without the run
+//! counts), or a `level nw ne sw se` line whose children refer back to earlier lines by number
+//! (with 0 meaning "all dead"). Identical subtrees only need to be written once, which is what
+//! makes huge, repetitive patterns compact. See
+//! http://golly.sourceforge.net/Help/formats.html#mc for the details.
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::pattern::Pattern;
+use std::collections::HashMap;
+
+pub(crate) const MACROCELL_HEADER: &str = "[M2]";
+
+/// Leaves are 8x8 cells, i.e. 2^3 cells on each side.
+const LEAF_LEVEL: u32 = 3;
+
+/// Beyond this level, cell coordinates would no longer fit in an `i64`.
+const MAX_LEVEL: u32 = 62;
+
+/// The rule written into the file when the pattern doesn't say which rule it uses.
+const DEFAULT_RULE: &str = "B3/S23";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// An 8x8 block of cells, one byte per row with the leftmost cell in the lowest bit.
+    Leaf([u8; 8]),
+    /// A 2^level square made of 4 nodes of the level below, as indexes into the list of nodes.
+    Branch { level: u32, children: [usize; 4] },
+}
+
+/// The quadtree described by a Macrocell file.
+///
+/// This is kept separate from `Pattern` so that backends which are themselves quadtrees can build
+/// their own tree straight from it with `fold` (see `GameOfLifeHashLife::load_macrocell`), instead
+/// of going via a list of every live cell, which for huge patterns would never fit in memory.
+#[derive(Clone, Debug)]
+pub(crate) struct MacrocellTree {
+    /// Every node in file order, so that node `n` in the file is at index `n`; index 0 holds a
+    /// placeholder for the empty node, which is never written out.
+    nodes: Vec<Node>,
+    pub rule: Option<String>,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+}
+
+impl MacrocellTree {
+    /// Parse the contents of a Macrocell file.
+    pub fn parse(contents: &str) -> Result<MacrocellTree, ParseError> {
+        let mut tree = MacrocellTree {
+            nodes: vec![Node::Leaf([0; 8])],
+            rule: None,
+            name: None,
+            author: None,
+            comments: Vec::new(),
+        };
+        let mut lines = (1..).zip(contents.lines());
+        match lines.next() {
+            Some((_, line)) if line.starts_with(MACROCELL_HEADER) => {}
+            _ => {
+                return Err(ParseError::new(
+                    1,
+                    1,
+                    ParseErrorKind::InvalidHeader(format!(
+                        "expected file to start with {}",
+                        MACROCELL_HEADER
+                    )),
+                ))
+            }
+        }
+
+        for (line_no, line) in lines {
+            let line = line.trim_end();
+            if let Some(rule) = line.strip_prefix("#R") {
+                tree.rule = Some(rule.trim().to_owned());
+            } else if let Some(name) = line.strip_prefix("#N") {
+                tree.name = Some(name.trim().to_owned());
+            } else if let Some(author) = line.strip_prefix("#O") {
+                tree.author = Some(author.trim().to_owned());
+            } else if let Some(comment) = line.strip_prefix("#C") {
+                tree.comments
+                    .push(comment.strip_prefix(' ').unwrap_or(comment).to_owned());
+            } else if line.is_empty() || line.starts_with('#') {
+                // other `#` lines are things like generation counts, which we don't need
+            } else if line.starts_with(['.', '*', '$']) {
+                tree.nodes.push(parse_leaf(line_no, line)?);
+            } else {
+                let node = tree.parse_branch(line_no, line)?;
+                tree.nodes.push(node);
+            }
+        }
+        Ok(tree)
+    }
+
+    fn parse_branch(&self, line_no: usize, line: &str) -> Result<Node, ParseError> {
+        let invalid = |column: usize, reason: String| {
+            ParseError::new(line_no, column, ParseErrorKind::InvalidNode(reason))
+        };
+
+        let mut column = 1;
+        let mut numbers = Vec::with_capacity(5);
+        for token in line.split(' ') {
+            if !token.is_empty() {
+                let number = token
+                    .parse::<usize>()
+                    .map_err(|_| invalid(column, format!("{:?} is not a node number", token)))?;
+                numbers.push((column, number));
+            }
+            column += token.chars().count() + 1;
+        }
+        let (level, children) = match numbers[..] {
+            [(_, level), nw, ne, sw, se] => (level, [nw, ne, sw, se]),
+            _ => return Err(invalid(1, "expected `level nw ne sw se`".to_owned())),
+        };
+        if level <= LEAF_LEVEL as usize || level > MAX_LEVEL as usize {
+            return Err(invalid(
+                1,
+                format!(
+                    "level must be between {} and {} (multi-state files aren't supported)",
+                    LEAF_LEVEL + 1,
+                    MAX_LEVEL
+                ),
+            ));
+        }
+        let level = level as u32;
+
+        let mut child_indexes = [0; 4];
+        for (child_index, &(column, child)) in child_indexes.iter_mut().zip(children.iter()) {
+            // node 0 is the empty node, which fits in anywhere
+            if child != 0 {
+                match self.nodes.get(child) {
+                    Some(node) if node_level(node) == level - 1 => {}
+                    Some(_) => {
+                        return Err(invalid(
+                            column,
+                            format!("node {} is not of level {}", child, level - 1),
+                        ))
+                    }
+                    None => {
+                        return Err(invalid(
+                            column,
+                            format!("node {} hasn't been defined yet", child),
+                        ))
+                    }
+                }
+            }
+            *child_index = child;
+        }
+        Ok(Node::Branch {
+            level,
+            children: child_indexes,
+        })
+    }
+
+    /// Build something out of each node in the tree, from the leaves up, and return what was built
+    /// for the root, or `None` if the tree is empty. `leaf` is given the rows of a leaf as for
+    /// `Node::Leaf`, `branch` the level of a branch and what was built for its four children, and
+    /// `empty` the level of any child that's all dead; each is also given `builder`, to build
+    /// with. Each node is only built once, however many times it's used.
+    pub fn fold<B, T: Copy>(
+        &self,
+        builder: &mut B,
+        leaf: impl Fn(&mut B, &[u8; 8]) -> T,
+        branch: impl Fn(&mut B, u32, [T; 4]) -> T,
+        empty: impl Fn(&mut B, u32) -> T,
+    ) -> Option<T> {
+        // children always come before their parents in the file, so one pass in order will do
+        let mut built: Vec<Option<T>> = vec![None];
+        for node in &self.nodes[1..] {
+            let value = match node {
+                Node::Leaf(rows) => leaf(builder, rows),
+                Node::Branch { level, children } => {
+                    let children = children.map(|child| match built[child] {
+                        Some(value) => value,
+                        None => empty(builder, level - 1),
+                    });
+                    branch(builder, *level, children)
+                }
+            };
+            built.push(Some(value));
+        }
+        built.pop().flatten()
+    }
+
+    /// The left, top, right and bottom edges of the smallest box containing every live cell (the
+    /// right and bottom ones being just past it), relative to the center of the root node like
+    /// `for_each_live_cell`'s coordinates, or `None` if the tree is empty. It's worked out a node at
+    /// a time, without going through the cells.
+    pub fn bounding_box(&self) -> Option<[i64; 4]> {
+        let leaf = |_: &mut (), rows: &[u8; 8]| {
+            let filled: Vec<i64> = (0..8).filter(|&y| rows[y as usize] != 0).collect();
+            let columns = rows.iter().fold(0, |columns, row| columns | row);
+            Some([
+                columns.trailing_zeros() as i64,
+                *filled.first()?,
+                8 - columns.leading_zeros() as i64,
+                *filled.last()? + 1,
+            ])
+        };
+        let branch = |_: &mut (), level: u32, children: [Option<[i64; 4]>; 4]| {
+            let half = 1i64 << (level - 1);
+            (0..4)
+                .filter_map(|quadrant| {
+                    let (dx, dy) = ((quadrant % 2) as i64 * half, (quadrant / 2) as i64 * half);
+                    children[quadrant].map(|[left, top, right, bottom]| {
+                        [left + dx, top + dy, right + dx, bottom + dy]
+                    })
+                })
+                .reduce(|a, b| {
+                    [
+                        a[0].min(b[0]),
+                        a[1].min(b[1]),
+                        a[2].max(b[2]),
+                        a[3].max(b[3]),
+                    ]
+                })
+        };
+        let [left, top, right, bottom] = self.fold(&mut (), leaf, branch, |_, _| None)??;
+        let half = 1i64 << (node_level(&self.nodes[self.nodes.len() - 1]) - 1);
+        Some([left - half, top - half, right - half, bottom - half])
+    }
+
+    /// Call `f` with the coordinates of every live cell in the tree. The coordinates are relative
+    /// to the center of the root node, which is how Golly positions Macrocell patterns.
+    pub fn for_each_live_cell(&self, f: &mut dyn FnMut(i64, i64)) {
+        let root = self.nodes.len() - 1;
+        if root == 0 {
+            return;
+        }
+        let half = 1i64 << (node_level(&self.nodes[root]) - 1);
+        self.visit(root, -half, -half, f);
+    }
+
+    fn visit(&self, index: usize, left: i64, top: i64, f: &mut dyn FnMut(i64, i64)) {
+        if index == 0 {
+            return;
+        }
+        match &self.nodes[index] {
+            Node::Leaf(rows) => {
+                for (y, row) in (0..).zip(rows.iter()) {
+                    for x in 0..8 {
+                        if row & (1 << x) != 0 {
+                            f(left + x, top + y);
+                        }
+                    }
+                }
+            }
+            Node::Branch { level, children } => {
+                let half = 1i64 << (level - 1);
+                self.visit(children[0], left, top, f);
+                self.visit(children[1], left + half, top, f);
+                self.visit(children[2], left, top + half, f);
+                self.visit(children[3], left + half, top + half, f);
+            }
+        }
+    }
+}
+
+fn node_level(node: &Node) -> u32 {
+    match node {
+        Node::Leaf(_) => LEAF_LEVEL,
+        Node::Branch { level, .. } => *level,
+    }
+}
+
+fn parse_leaf(line_no: usize, line: &str) -> Result<Node, ParseError> {
+    let mut rows = [0u8; 8];
+    let (mut x, mut y) = (0, 0);
+    for (column, c) in (1..).zip(line.chars()) {
+        match c {
+            '.' | '*' if x < 8 && y < 8 => {
+                if c == '*' {
+                    rows[y] |= 1 << x;
+                }
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            c => {
+                return Err(ParseError::new(
+                    line_no,
+                    column,
+                    ParseErrorKind::UnexpectedCharacter(c),
+                ))
+            }
+        }
+    }
+    Ok(Node::Leaf(rows))
+}
+
+/// Parse the contents of a Macrocell file into a list of cells.
+pub(crate) fn parse(contents: &str) -> Result<Pattern, ParseError> {
+    let tree = MacrocellTree::parse(contents)?;
+    let mut live_cells = Vec::new();
+    tree.for_each_live_cell(&mut |x, y| live_cells.push((x, y)));
+    Ok(Pattern {
+        rule: tree.rule,
+        name: tree.name,
+        author: tree.author,
+        comments: tree.comments,
+        ..Pattern::from_coordinates(live_cells)
+    })
+}
+
+/// Parse the details of a Macrocell file, and the size of its pattern, leaving out the live cells.
+pub(crate) fn parse_without_cells(contents: &str) -> Result<Pattern, ParseError> {
+    let tree = MacrocellTree::parse(contents)?;
+    let bounding_box = tree.bounding_box();
+    Ok(Pattern {
+        width: bounding_box.map_or(0, |[left, _, right, _]| (right - left) as usize),
+        height: bounding_box.map_or(0, |[_, top, _, bottom]| (bottom - top) as usize),
+        origin: bounding_box.map(|[left, top, _, _]| (left, top)),
+        rule: tree.rule,
+        name: tree.name,
+        author: tree.author,
+        comments: tree.comments,
+        ..Pattern::default()
+    })
+}
+
+/// Encode the given pattern as the contents of a Macrocell file.
+///
+/// Golly always centers Macrocell patterns on the origin, so the pattern's origin is not kept.
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut level = LEAF_LEVEL;
+    while (1usize << level) < pattern.width.max(pattern.height) {
+        level += 1;
+    }
+
+    let mut builder = TreeBuilder::default();
+    let root = builder.build(level, 0, 0, &pattern.live_cells);
+
+    let mut out = format!(
+        "{} (conway)\n#R {}\n",
+        MACROCELL_HEADER,
+        pattern.rule.as_deref().unwrap_or(DEFAULT_RULE)
+    );
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("#O {}\n", author));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {}\n", comment));
+    }
+    for line in &builder.lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    // an empty pattern still needs a root node, so give it an empty leaf
+    if root == 0 {
+        out.push_str("$\n");
+    }
+    out
+}
+
+/// Builds up the lines of a Macrocell file, writing out each distinct node only once.
+#[derive(Default)]
+struct TreeBuilder {
+    lines: Vec<String>,
+    node_numbers: HashMap<Node, usize>,
+}
+
+impl TreeBuilder {
+    /// Add the node covering the 2^level square with its top left at `left`, `top` (which must
+    /// contain all of `cells`), returning the node's number.
+    fn build(&mut self, level: u32, left: usize, top: usize, cells: &[(usize, usize)]) -> usize {
+        if cells.is_empty() {
+            return 0;
+        }
+        let node = if level == LEAF_LEVEL {
+            let mut rows = [0u8; 8];
+            for &(x, y) in cells {
+                rows[y - top] |= 1 << (x - left);
+            }
+            Node::Leaf(rows)
+        } else {
+            let half = 1 << (level - 1);
+            let mut quadrants = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+            for &(x, y) in cells {
+                let quadrant = (x >= left + half) as usize + 2 * (y >= top + half) as usize;
+                quadrants[quadrant].push((x, y));
+            }
+            let children = [
+                self.build(level - 1, left, top, &quadrants[0]),
+                self.build(level - 1, left + half, top, &quadrants[1]),
+                self.build(level - 1, left, top + half, &quadrants[2]),
+                self.build(level - 1, left + half, top + half, &quadrants[3]),
+            ];
+            Node::Branch { level, children }
+        };
+
+        if let Some(&number) = self.node_numbers.get(&node) {
+            return number;
+        }
+        self.lines.push(format_node(&node));
+        self.node_numbers.insert(node, self.lines.len());
+        self.lines.len()
+    }
+}
+
+fn format_node(node: &Node) -> String {
+    match node {
+        Node::Leaf(rows) => {
+            // rows stop at their last live cell, and rows after the last live one are left out
+            let last_row = rows.iter().rposition(|&row| row != 0).unwrap_or(0);
+            let mut line = String::new();
+            for row in &rows[..=last_row] {
+                let row_length = 8 - row.leading_zeros() as usize;
+                line.extend((0..row_length).map(|x| if row & (1 << x) != 0 { '*' } else { '.' }));
+                line.push('$');
+            }
+            line
+        }
+        Node::Branch { level, children } => format!(
+            "{} {} {} {} {}",
+            level, children[0], children[1], children[2], children[3]
+        ),
+    }
+}
+
+#[cfg(test)]
+mod macrocell_test {
+    use super::{parse, parse_without_cells, write};
+    use crate::error::{ParseError, ParseErrorKind};
+    use crate::pattern::Pattern;
+    use std::path::PathBuf;
+
+    const GLIDER: &str = "[M2] (golly 2.0)\n#R B3/S23\n$$..*$...*$.***$\n4 0 0 0 1\n";
+
+    #[test]
+    fn parses_glider() {
+        let pattern = parse(GLIDER).unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(
+            pattern.live_cells,
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn reports_missing_header() {
+        let err = parse("$$..*$...*$.***$\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+    }
+
+    #[test]
+    fn reports_undefined_child() {
+        assert_eq!(
+            parse("[M2]\n$$..*$...*$.***$\n4 0 1 0 2\n").unwrap_err(),
+            ParseError::new(
+                3,
+                9,
+                ParseErrorKind::InvalidNode("node 2 hasn't been defined yet".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn reports_child_of_wrong_level() {
+        let err = parse("[M2]\n*$\n4 1 0 0 0\n5 0 1 0 1\n").unwrap_err();
+        assert_eq!((err.line, err.column), (4, 5));
+    }
+
+    #[test]
+    fn shares_repeated_subtrees() {
+        // a 256x256 grid of blocks is mostly the same few nodes over and over
+        let mut live_cells = Vec::new();
+        for x in (0..256).step_by(4) {
+            for y in (0..256).step_by(4) {
+                live_cells.extend_from_slice(&[(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]);
+            }
+        }
+        let pattern = Pattern::from_coordinates(live_cells);
+        let written = write(&pattern);
+        // header and rule, one leaf, then one node for each level from 4 to 8
+        assert_eq!(written.lines().count(), 2 + 1 + 5);
+
+        let mut reloaded = parse(&written).unwrap();
+        assert_eq!(
+            (reloaded.width, reloaded.height),
+            (pattern.width, pattern.height)
+        );
+        let mut expected_cells = pattern.live_cells;
+        expected_cells.sort_unstable();
+        reloaded.live_cells.sort_unstable();
+        assert_eq!(reloaded.live_cells, expected_cells);
+    }
+
+    #[test]
+    fn round_trips_every_bundled_pattern() {
+        let patterns_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("patterns");
+        for entry in std::fs::read_dir(patterns_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let original = crate::rle::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            let mut original_cells = original.live_cells.clone();
+            original_cells.sort_unstable();
+
+            let reloaded = parse(&write(&original)).unwrap();
+            let mut reloaded_cells = reloaded.live_cells.clone();
+            reloaded_cells.sort_unstable();
+
+            // the writer doesn't keep empty margins, so shift the originals to match
+            let min_x = original_cells.iter().map(|c| c.0).min().unwrap();
+            let min_y = original_cells.iter().map(|c| c.1).min().unwrap();
+            for cell in original_cells.iter_mut() {
+                *cell = (cell.0 - min_x, cell.1 - min_y);
+            }
+            assert_eq!(reloaded_cells, original_cells, "{:?} changed", path);
+
+            // the size and details come out the same without listing the cells
+            assert_eq!(
+                parse_without_cells(&write(&original)).unwrap(),
+                Pattern {
+                    live_cells: Vec::new(),
+                    ..reloaded
+                },
+                "{:?} read differently without its cells",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::error::{ParseError, PatternError};
+use crate::format::PatternFormat;
+use crate::game_of_life::GameOfLife;
+use crate::macrocell;
+use crate::placement::{Flip, Placement, Position, Rotation};
+use crate::rule::{Rule, RuleParseError};
+use crate::topology::BoundedGrid;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A Game of Life pattern, along with the details that pattern files carry about it (its name,
+/// who found it, and so on), independent of any particular game.
+///
+/// Load one from a file with `Pattern::load`, then put it into a game with `apply`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    /// Width of the pattern's bounding box.
+    pub width: usize,
+    /// Height of the pattern's bounding box.
+    pub height: usize,
+    /// Coordinates of every live cell, relative to the top left of the pattern's bounding box.
+    pub live_cells: Vec<(usize, usize)>,
+    /// The rule the pattern is meant to be run with (e.g. `B3/S23`), if the file said.
+    pub rule: Option<String>,
+    /// The pattern's name (`#N` in RLE files).
+    pub name: Option<String>,
+    /// Who found or made the pattern (`#O` in RLE files).
+    pub author: Option<String>,
+    /// Any other comment lines, such as descriptions or links (`#C` in RLE files).
+    pub comments: Vec<String>,
+    /// Where the top left of the pattern is meant to be, for formats which position patterns
+    /// relative to an origin (such as `#CXRLE Pos=` in RLE files, or Life 1.06 coordinates).
+    pub origin: Option<(i64, i64)>,
+}
+
+impl Pattern {
+    /// Read the pattern in the given file, working out its format from the file's contents and
+    /// extension.
+    pub fn load(pattern_filename: &Path) -> Result<Pattern, PatternError> {
+        let contents = fs::read_to_string(pattern_filename)?;
+        let format = PatternFormat::detect(pattern_filename, &contents)?;
+        Ok(Pattern::parse(&contents, format)?)
+    }
+
+    /// Parse the contents of a pattern file that's in the given format.
+    pub fn parse(contents: &str, format: PatternFormat) -> Result<Pattern, ParseError> {
+        format.parse(contents)
+    }
+
+    /// Like `parse`, but leaving `live_cells` empty, for when the cells are going to be put into a
+    /// game some other way (see `GameOfLifeHashLife::load_macrocell`). Macrocell files are read
+    /// without going through their cells at all, so this works for patterns far too big to list.
+    pub fn parse_without_cells(
+        contents: &str,
+        format: PatternFormat,
+    ) -> Result<Pattern, ParseError> {
+        match format {
+            PatternFormat::Macrocell => macrocell::parse_without_cells(contents),
+            _ => Ok(Pattern {
+                live_cells: Vec::new(),
+                ..format.parse(contents)?
+            }),
+        }
+    }
+
+    /// The rule the pattern is meant to be run with, or `None` if the file didn't say. Fails if
+    /// the file names a rule that can't be parsed.
+    pub fn parsed_rule(&self) -> Result<Option<Rule>, RuleParseError> {
+        self.rule
+            .as_deref()
+            .map(|rule| BoundedGrid::split_from_rule(rule)?.0.parse())
+            .transpose()
+    }
+
+    /// The bounded grid the pattern is meant to be run on, if its rule ends with one (such as the
+    /// `:T100,80` in `B3/S23:T100,80`). Fails if that part of the rule can't be parsed.
+    pub fn bounded_grid(&self) -> Result<Option<BoundedGrid>, RuleParseError> {
+        match self.rule.as_deref().map(BoundedGrid::split_from_rule) {
+            Some(split) => Ok(split?.1),
+            None => Ok(None),
+        }
+    }
+
+    /// Capture the live cells of the given game, cropped to the smallest box that contains them
+    /// all. The pattern's origin is where that box was on the board.
+    pub fn from_game(game: &dyn GameOfLife) -> Pattern {
+        let mut live_cells = Vec::new();
+        if let Some(bounds) = game.live_bounding_box() {
+            for y in bounds.y..bounds.y + bounds.height as i64 {
+                for x in bounds.x..bounds.x + bounds.width as i64 {
+                    if game.cell_state_at(x, y) == Some(1) {
+                        live_cells.push((x, y));
+                    }
+                }
+            }
+        }
+        Pattern::from_coordinates(live_cells)
+    }
+
+    /// Build a pattern from live cells at arbitrary (possibly negative) coordinates, as used by
+    /// formats that position cells relative to some origin. The cells are shifted so that the
+    /// pattern's top left is at 0, 0 and the pattern is cropped to fit them exactly; the original
+    /// position of the top left is kept as the pattern's origin.
+    pub(crate) fn from_coordinates(live_cells: Vec<(i64, i64)>) -> Pattern {
+        if live_cells.is_empty() {
+            return Pattern::default();
+        }
+        let min_x = live_cells.iter().map(|&(x, _)| x).min().unwrap();
+        let max_x = live_cells.iter().map(|&(x, _)| x).max().unwrap();
+        let min_y = live_cells.iter().map(|&(_, y)| y).min().unwrap();
+        let max_y = live_cells.iter().map(|&(_, y)| y).max().unwrap();
+
+        Pattern {
+            width: (max_x - min_x + 1) as usize,
+            height: (max_y - min_y + 1) as usize,
+            live_cells: live_cells
+                .into_iter()
+                .map(|(x, y)| ((x - min_x) as usize, (y - min_y) as usize))
+                .collect(),
+            origin: Some((min_x, min_y)),
+            ..Pattern::default()
+        }
+    }
+
+    /// Encode the pattern (including its name, comments and so on, where the format has room for
+    /// them) as the contents of a pattern file in the given format.
+    pub fn encode(&self, format: PatternFormat) -> String {
+        format.write(self)
+    }
+
+    /// Save the pattern to a file, picking the format based on the file extension (and falling
+    /// back to RLE for unknown extensions).
+    pub fn save(&self, pattern_filename: &Path) -> io::Result<()> {
+        let format = PatternFormat::from_extension(pattern_filename).unwrap_or(PatternFormat::Rle);
+        fs::write(pattern_filename, self.encode(format))
+    }
+
+    /// Draw the pattern out as one string per row, for the formats that store a picture of the
+    /// pattern. Each row stops at its rightmost live cell, so empty rows are empty strings.
+    pub(crate) fn draw_rows(&self, dead: char, alive: char) -> Vec<String> {
+        let mut rows = vec![Vec::new(); self.height];
+        for &(x, y) in &self.live_cells {
+            let row = &mut rows[y];
+            if row.len() <= x {
+                row.resize(x + 1, dead);
+            }
+            row[x] = alive;
+        }
+        rows.into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
+    }
+
+    /// Make a copy of the pattern that has been flipped and then rotated.
+    pub fn transformed(&self, rotation: Rotation, flip: Flip) -> Pattern {
+        let (width, height) = if rotation.swaps_dimensions() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let live_cells = self
+            .live_cells
+            .iter()
+            .map(|&cell| {
+                let flipped = flip.apply(cell, self.width, self.height);
+                rotation.apply(flipped, self.width, self.height)
+            })
+            .collect();
+        Pattern {
+            width,
+            height,
+            live_cells,
+            ..self.clone()
+        }
+    }
+
+    /// Bring the pattern's live cells to life in the given game, with the top left of the pattern
+    /// at the top left of the board. Cells that fall off the edge of the board are dropped.
+    pub fn apply(&self, game: &mut dyn GameOfLife) {
+        self.apply_with(game, &Placement::default());
+    }
+
+    /// Bring the pattern's live cells to life in the given game, positioned and oriented as the
+    /// placement says. Cells that fall off the edge of the board are dropped.
+    pub fn apply_with(&self, game: &mut dyn GameOfLife, placement: &Placement) {
+        let pattern = self.transformed(placement.rotation, placement.flip);
+        let (offset_x, offset_y) = pattern.offset_for(&*game, placement.position);
+
+        for &(x, y) in &pattern.live_cells {
+            // out of bounds cells get skipped
+            game.set_cell_at(x as i64 + offset_x, y as i64 + offset_y, true);
+        }
+    }
+
+    /// Check that the whole pattern would land on the board if it were applied to the given game
+    /// with the given placement, i.e. that `apply_with` wouldn't have to leave any of it off.
+    pub fn check_fits(
+        &self,
+        game: &dyn GameOfLife,
+        placement: &Placement,
+    ) -> Result<(), PatternError> {
+        let pattern = self.transformed(placement.rotation, placement.flip);
+        let (x, y) = pattern.offset_for(game, placement.position);
+        // boards are rectangles (or go on forever), so the pattern fits if both its top left and
+        // bottom right corners land on the board
+        let (right, bottom) = (x + pattern.width as i64 - 1, y + pattern.height as i64 - 1);
+        let fits = pattern.width == 0
+            || pattern.height == 0
+            || (game.cell_state_at(x, y).is_some() && game.cell_state_at(right, bottom).is_some());
+        if fits {
+            Ok(())
+        } else {
+            Err(PatternError::DoesNotFit {
+                pattern_width: pattern.width,
+                pattern_height: pattern.height,
+                x,
+                y,
+                board_width: game.width(),
+                board_height: game.height(),
+            })
+        }
+    }
+
+    /// Where the top left of this pattern goes when it's positioned on the given game's board
+    /// (without rotating or flipping it first).
+    pub fn offset_for(&self, game: &dyn GameOfLife, position: Position) -> (i64, i64) {
+        match position {
+            Position::Offset(x, y) => (x, y),
+            Position::Centered => (
+                (game.width() as i64 - self.width as i64) / 2,
+                (game.height() as i64 - self.height as i64) / 2,
+            ),
+        }
+    }
+}
+
+/// Remove the single space that conventionally separates a comment from what marks it out as one
+/// (`#C` in RLE files, or `!` in plaintext ones), while keeping any further indentation the author
+/// wanted.
+pub(crate) fn strip_one_space(comment: &str) -> &str {
+    comment.strip_prefix(' ').unwrap_or(comment)
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Where and which way round a pattern should be put onto a board.
+///
+/// The pattern is flipped first, then rotated, then positioned; so e.g. a horizontal flip with a
+/// 90 degree rotation mirrors the pattern left-to-right before turning it. The default is the
+/// pattern as-is, at the top left of the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub position: Position,
+    pub rotation: Rotation,
+    pub flip: Flip,
+}
+
+/// Where on the board a pattern should go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// Put the top left of the pattern at this cell. Negative values are allowed, in which case
+    /// the part of the pattern that's off the edge of the board is left off.
+    Offset(i64, i64),
+    /// Put the middle of the pattern at the middle of the board.
+    Centered,
+}
+
+impl Default for Position {
+    fn default() -> Position {
+        Position::Offset(0, 0)
+    }
+}
+
+impl FromStr for Position {
+    type Err = PlacementParseError;
+
+    /// Parse an offset of the form `x,y`, such as `10,-5`.
+    fn from_str(s: &str) -> Result<Position, PlacementParseError> {
+        let error = || PlacementParseError(format!("{:?} is not of the form x,y", s));
+        let (x, y) = s.split_once(',').ok_or_else(error)?;
+        let x = x.trim().parse().map_err(|_| error())?;
+        let y = y.trim().parse().map_err(|_| error())?;
+        Ok(Position::Offset(x, y))
+    }
+}
+
+/// How far a pattern should be turned clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+impl Rotation {
+    /// Where the cell at `x`, `y` in a `width` by `height` pattern ends up after rotating it.
+    pub(crate) fn apply(
+        self,
+        (x, y): (usize, usize),
+        width: usize,
+        height: usize,
+    ) -> (usize, usize) {
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Clockwise90 => (height - 1 - y, x),
+            Rotation::Clockwise180 => (width - 1 - x, height - 1 - y),
+            Rotation::Clockwise270 => (y, width - 1 - x),
+        }
+    }
+
+    /// Does rotating this way turn a `width` by `height` pattern into a `height` by `width` one?
+    pub(crate) fn swaps_dimensions(self) -> bool {
+        matches!(self, Rotation::Clockwise90 | Rotation::Clockwise270)
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = PlacementParseError;
+
+    /// Parse a rotation given in degrees clockwise: 0, 90, 180 or 270.
+    fn from_str(s: &str) -> Result<Rotation, PlacementParseError> {
+        match s.trim() {
+            "0" => Ok(Rotation::None),
+            "90" => Ok(Rotation::Clockwise90),
+            "180" => Ok(Rotation::Clockwise180),
+            "270" => Ok(Rotation::Clockwise270),
+            _ => Err(PlacementParseError(format!(
+                "{:?} is not a rotation of 0, 90, 180 or 270 degrees",
+                s
+            ))),
+        }
+    }
+}
+
+/// Which way a pattern should be mirrored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Flip {
+    #[default]
+    None,
+    /// Mirror left to right.
+    Horizontal,
+    /// Mirror top to bottom.
+    Vertical,
+}
+
+impl Flip {
+    /// Where the cell at `x`, `y` in a `width` by `height` pattern ends up after flipping it.
+    pub(crate) fn apply(
+        self,
+        (x, y): (usize, usize),
+        width: usize,
+        height: usize,
+    ) -> (usize, usize) {
+        match self {
+            Flip::None => (x, y),
+            Flip::Horizontal => (width - 1 - x, y),
+            Flip::Vertical => (x, height - 1 - y),
+        }
+    }
+}
+
+impl FromStr for Flip {
+    type Err = PlacementParseError;
+
+    /// Parse `none`, `horizontal` or `vertical` (or just `h` or `v`), in any case.
+    fn from_str(s: &str) -> Result<Flip, PlacementParseError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Flip::None),
+            "horizontal" | "h" => Ok(Flip::Horizontal),
+            "vertical" | "v" => Ok(Flip::Vertical),
+            _ => Err(PlacementParseError(format!(
+                "{:?} is not a flip of horizontal or vertical",
+                s
+            ))),
+        }
+    }
+}
+
+/// The reason a placement option couldn't be parsed from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacementParseError(String);
+
+impl fmt::Display for PlacementParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PlacementParseError {}
+
+#[cfg(test)]
+mod placement_test {
+    use super::{Flip, Position, Rotation};
+    use crate::pattern::Pattern;
+    use crate::{GameOfLife, GameOfLifeSolution, Placement};
+
+    /// A glider heading down and to the right.
+    fn glider() -> Pattern {
+        Pattern {
+            width: 3,
+            height: 3,
+            live_cells: vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)],
+            ..Pattern::default()
+        }
+    }
+
+    fn sorted_cells(pattern: &Pattern) -> Vec<(usize, usize)> {
+        let mut cells = pattern.live_cells.clone();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn rotating_swaps_dimensions() {
+        let pattern = Pattern {
+            width: 3,
+            height: 1,
+            live_cells: vec![(0, 0), (2, 0)],
+            ..Pattern::default()
+        };
+        let rotated = pattern.transformed(Rotation::Clockwise90, Flip::None);
+        assert_eq!((rotated.width, rotated.height), (1, 3));
+        assert_eq!(sorted_cells(&rotated), vec![(0, 0), (0, 2)]);
+    }
+
+    #[test]
+    fn four_quarter_turns_make_a_full_turn() {
+        let mut pattern = glider();
+        for _ in 0..4 {
+            pattern = pattern.transformed(Rotation::Clockwise90, Flip::None);
+        }
+        assert_eq!(sorted_cells(&pattern), sorted_cells(&glider()));
+    }
+
+    #[test]
+    fn rotating_glider_changes_its_heading() {
+        // turning a down-right glider 90 degrees clockwise makes it head down-left
+        let rotated = glider().transformed(Rotation::Clockwise90, Flip::None);
+        assert_eq!(
+            sorted_cells(&rotated),
+            vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 1)]
+        );
+        // as does mirroring it left to right, albeit in a different phase
+        let flipped = glider().transformed(Rotation::None, Flip::Horizontal);
+        assert_eq!(
+            sorted_cells(&flipped),
+            vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn flips_before_rotating() {
+        let both = glider().transformed(Rotation::Clockwise180, Flip::Vertical);
+        let step_by_step = glider()
+            .transformed(Rotation::None, Flip::Vertical)
+            .transformed(Rotation::Clockwise180, Flip::None);
+        assert_eq!(sorted_cells(&both), sorted_cells(&step_by_step));
+    }
+
+    #[test]
+    fn applies_at_offset_and_clips() {
+        let mut game = GameOfLifeSolution::new(4, 4);
+        glider().apply_with(
+            &mut game,
+            &Placement {
+                position: Position::Offset(-1, 2),
+                ..Placement::default()
+            },
+        );
+        // only the top two rows of the glider fit on the board
+        let live: Vec<_> = (0..4)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .filter(|&(x, y)| game.is_cell_alive(x, y) == Some(true))
+            .collect();
+        assert_eq!(live, vec![(0, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn applies_centered() {
+        let mut game = GameOfLifeSolution::new(7, 7);
+        glider().apply_with(
+            &mut game,
+            &Placement {
+                position: Position::Centered,
+                ..Placement::default()
+            },
+        );
+        assert_eq!(game.is_cell_alive(3, 2), Some(true));
+        assert_eq!(game.is_cell_alive(3, 4), Some(true));
+        assert_eq!(game.is_cell_alive(3, 3), Some(false));
+    }
+
+    #[test]
+    fn checks_whether_pattern_fits() {
+        let game = GameOfLifeSolution::new(5, 3);
+        assert!(glider().check_fits(&game, &Placement::default()).is_ok());
+
+        let off_the_edge = Placement {
+            position: Position::Offset(3, 0),
+            ..Placement::default()
+        };
+        assert!(glider().check_fits(&game, &off_the_edge).is_err());
+
+        let wide = Pattern {
+            width: 6,
+            height: 1,
+            live_cells: vec![(0, 0), (5, 0)],
+            ..Pattern::default()
+        };
+        let err = wide.check_fits(&game, &Placement::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the 6x1 pattern doesn't fit on the 5x3 board with its top left at 0, 0"
+        );
+        // but turned on its side it's too tall instead
+        let turned = Placement {
+            rotation: Rotation::Clockwise90,
+            ..Placement::default()
+        };
+        assert!(wide.check_fits(&game, &turned).is_err());
+    }
+
+    #[test]
+    fn parses_command_line_values() {
+        assert_eq!("12,-3".parse(), Ok(Position::Offset(12, -3)));
+        assert!("12".parse::<Position>().is_err());
+        assert_eq!("270".parse(), Ok(Rotation::Clockwise270));
+        assert!("45".parse::<Rotation>().is_err());
+        assert_eq!("Horizontal".parse(), Ok(Flip::Horizontal));
+        assert_eq!("v".parse(), Ok(Flip::Vertical));
+    }
+}
@@ -0,0 +1,123 @@
+//! Reading and writing of plaintext pattern files (usually with a `.cells` extension), which draw
+//! the pattern out directly using `.` for dead cells and `O` for live cells. See
+//! http://www.conwaylife.com/wiki/Plaintext for the details of the format.
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::pattern::{strip_one_space, Pattern};
+
+/// Parse the contents of a plaintext file.
+pub(crate) fn parse(contents: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let (mut width, mut height) = (0, 0);
+    let mut y = 0;
+    for (line_no, line) in (1..).zip(contents.lines()) {
+        if let Some(comment) = line.strip_prefix('!') {
+            if let Some(name) = comment.strip_prefix("Name:") {
+                pattern.name = Some(name.trim().to_owned());
+            } else if let Some(author) = comment.strip_prefix("Author:") {
+                pattern.author = Some(author.trim().to_owned());
+            } else if !comment.trim().is_empty() {
+                pattern
+                    .comments
+                    .push(strip_one_space(comment.trim_end()).to_owned());
+            }
+            continue;
+        }
+        let line = line.trim_end();
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                // `*` isn't strictly part of the format, but it's common enough to be worth reading
+                'O' | '*' => pattern.live_cells.push((x, y)),
+                c => {
+                    return Err(ParseError::new(
+                        line_no,
+                        x + 1,
+                        ParseErrorKind::UnexpectedCharacter(c),
+                    ))
+                }
+            }
+        }
+        y += 1;
+        // blank lines at the end of the file are not part of the pattern, but blank lines in the
+        // middle are empty rows
+        if !line.is_empty() {
+            width = width.max(line.chars().count());
+            height = y;
+        }
+    }
+
+    pattern.width = width;
+    pattern.height = height;
+    Ok(pattern)
+}
+
+/// Encode the given pattern as the contents of a plaintext file.
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("!Name: {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("!Author: {}\n", author));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("! {}\n", comment));
+    }
+    for row in pattern.draw_rows('.', 'O') {
+        // empty rows still need a `.` so that they don't look like the end of the file
+        out.push_str(if row.is_empty() { "." } else { &row });
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod plaintext_test {
+    use super::{parse, write};
+    use crate::error::{ParseError, ParseErrorKind};
+
+    #[test]
+    fn parses_glider_with_comments() {
+        let pattern = parse(
+            "!Name: Glider\n!Author: Richard K. Guy\n!\n! The smallest spaceship.\n.O\n..O\nOOO\n",
+        )
+        .unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(pattern.comments, vec!["The smallest spaceship."]);
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(
+            pattern.live_cells,
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn keeps_blank_rows_in_the_middle() {
+        let pattern = parse("O\n\n.O\n\n").unwrap();
+        assert_eq!((pattern.width, pattern.height), (2, 3));
+        assert_eq!(pattern.live_cells, vec![(0, 0), (1, 2)]);
+    }
+
+    #[test]
+    fn reports_position_of_unexpected_character() {
+        assert_eq!(
+            parse("!Name: Oops\n.O\n.xO\n").unwrap_err(),
+            ParseError::new(3, 2, ParseErrorKind::UnexpectedCharacter('x'))
+        );
+    }
+
+    #[test]
+    fn round_trips_through_writer() {
+        let pattern =
+            parse("!Name: Odd\n! Not a real pattern\n!   (honest)\n..O\n\nO.O\n").unwrap();
+        assert_eq!(pattern.comments, vec!["Not a real pattern", "  (honest)"]);
+        let written = write(&pattern);
+        assert_eq!(
+            written,
+            "!Name: Odd\n! Not a real pattern\n!   (honest)\n..O\n.\nO.O\n"
+        );
+        assert_eq!(parse(&written).unwrap(), pattern);
+    }
+}
@@ -0,0 +1,567 @@
+//! Reading and writing of Run Length Encoded (RLE) pattern files, which is how most Game of Life
+//! patterns are shared. See http://www.conwaylife.com/w/index.php?title=Run_Length_Encoded for
+//! the details of the format.
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::pattern::{strip_one_space, Pattern};
+
+/// Lines of pattern data are wrapped so they're no longer than this, to match the files that
+/// conwaylife.com and Golly produce.
+const MAX_LINE_LENGTH: usize = 70;
+
+/// The rule written into the header when the pattern doesn't say which rule it uses.
+const DEFAULT_RULE: &str = "B3/S23";
+
+/// Parse the contents of an RLE file.
+pub(crate) fn parse(contents: &str) -> Result<Pattern, ParseError> {
+    // number lines from 1 so that errors match what people see in their editors
+    let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    // comments and blank lines can appear before the header, so collect what we can from the
+    // comments while looking for it
+    let mut pattern = Pattern::default();
+    let mut last_line_no = 0;
+    let (header_line_no, header_line) = loop {
+        match lines.next() {
+            Some((line_no, line)) if is_comment_or_blank(line) => {
+                parse_comment(line_no, line.trim(), &mut pattern)?;
+                last_line_no = line_no;
+            }
+            Some((line_no, line)) if line.trim_start().starts_with('x') => break (line_no, line),
+            Some((line_no, _)) => {
+                return Err(ParseError::new(line_no, 1, ParseErrorKind::MissingHeader))
+            }
+            None => {
+                return Err(ParseError::new(
+                    last_line_no + 1,
+                    1,
+                    ParseErrorKind::MissingHeader,
+                ))
+            }
+        }
+    };
+    let (width, height, rule) = parse_header(header_line_no, header_line)?;
+    pattern.width = width;
+    pattern.height = height;
+    // a rule in the header line takes precedence over one from an old-style `#r` comment
+    if rule.is_some() {
+        pattern.rule = rule;
+    }
+
+    let mut live_cells = Vec::new();
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut run_count: Option<usize> = None;
+    let mut end_of_data = (header_line_no, header_line.chars().count() + 1);
+    for (line_no, line) in lines {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        for (column, c) in (1..).zip(line.chars()) {
+            let invalid_run_count =
+                ParseError::new(line_no, column, ParseErrorKind::InvalidRunCount);
+            match c {
+                '0'..='9' => {
+                    let digit = c.to_digit(10).unwrap() as usize;
+                    run_count = Some(
+                        run_count
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|n| n.checked_add(digit))
+                            .ok_or(invalid_run_count)?,
+                    );
+                }
+                // multi-state files (like Golly writes for Generations rules) use `.` for dead
+                // cells and `A` for live ones, which are fine, but cells in the states after
+                // those (`B` to `X`, or two letters from `pA` on) are dying, which patterns can't
+                // hold
+                'B'..='X' | 'p'..='y' => {
+                    return Err(ParseError::new(
+                        line_no,
+                        column,
+                        ParseErrorKind::DyingCell(c),
+                    ))
+                }
+                'b' | '.' | 'o' | 'A' | '$' | '!' => {
+                    let run = run_count.take().unwrap_or(1);
+                    if run == 0 {
+                        return Err(invalid_run_count);
+                    }
+                    match c {
+                        'b' | '.' => x = x.saturating_add(run),
+                        'o' | 'A' => {
+                            if y >= height || x.saturating_add(run) > width {
+                                // report the first cell of the run that doesn't fit
+                                let bad_x = if y >= height { x } else { x.max(width) };
+                                return Err(ParseError::new(
+                                    line_no,
+                                    column,
+                                    ParseErrorKind::CellOutOfBounds { x: bad_x, y },
+                                ));
+                            }
+                            live_cells.extend((x..x + run).map(|cell_x| (cell_x, y)));
+                            x += run;
+                        }
+                        '$' => {
+                            x = 0;
+                            y = y.saturating_add(run);
+                        }
+                        _ => {
+                            // we hit the `!`, so anything after it is free-form text to ignore
+                            pattern.live_cells = live_cells;
+                            return Ok(pattern);
+                        }
+                    }
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    return Err(ParseError::new(
+                        line_no,
+                        column,
+                        ParseErrorKind::UnexpectedCharacter(c),
+                    ))
+                }
+            }
+        }
+        end_of_data = (line_no, line.chars().count() + 1);
+    }
+
+    Err(ParseError::new(
+        end_of_data.0,
+        end_of_data.1,
+        ParseErrorKind::MissingTerminator,
+    ))
+}
+
+fn is_comment_or_blank(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Pick out whatever details about the pattern a `#` comment line gives.
+fn parse_comment(line_no: usize, line: &str, pattern: &mut Pattern) -> Result<(), ParseError> {
+    // `#CXRLE` has to be checked before `#C`, since otherwise it'd look like a regular comment
+    if let Some(extensions) = line.strip_prefix("#CXRLE") {
+        let mut column = "#CXRLE".len() + 1;
+        for part in extensions.split(' ') {
+            if let Some(position) = part.strip_prefix("Pos=") {
+                pattern.origin = Some(parse_position(line_no, column + 4, position, ',')?);
+            }
+            column += part.chars().count() + 1;
+        }
+    } else if let Some(comment) = line.strip_prefix("#C").or_else(|| line.strip_prefix("#c")) {
+        pattern.comments.push(strip_one_space(comment).to_owned());
+    } else if let Some(name) = line.strip_prefix("#N") {
+        pattern.name = Some(name.trim().to_owned());
+    } else if let Some(author) = line.strip_prefix("#O") {
+        pattern.author = Some(author.trim().to_owned());
+    } else if let Some(rule) = line.strip_prefix("#r") {
+        pattern.rule = Some(rule.trim().to_owned());
+    } else if let Some(position) = line.strip_prefix("#P").or_else(|| line.strip_prefix("#R")) {
+        // old-style top left coordinates, as written by XLife
+        let first_column = 3 + position.chars().take_while(|c| c.is_whitespace()).count();
+        pattern.origin = Some(parse_position(line_no, first_column, position.trim(), ' ')?);
+    }
+    Ok(())
+}
+
+/// Parse an `x<separator>y` pair of coordinates that starts at the given column.
+fn parse_position(
+    line_no: usize,
+    column: usize,
+    text: &str,
+    separator: char,
+) -> Result<(i64, i64), ParseError> {
+    let invalid = || {
+        ParseError::new(
+            line_no,
+            column,
+            ParseErrorKind::InvalidNumber(format!("{:?} is not a valid position", text)),
+        )
+    };
+    let (x, y) = text.split_once(separator).ok_or_else(invalid)?;
+    let x = x.trim().parse().map_err(|_| invalid())?;
+    let y = y.trim().parse().map_err(|_| invalid())?;
+    Ok((x, y))
+}
+
+/// Parse a header line of the form `x = m, y = n, rule = abc` (where the rule is optional) into
+/// the width, height and rule it describes. The rule runs to the end of the line, since some
+/// rules (such as Larger than Life ones) have commas in them.
+fn parse_header(line_no: usize, line: &str) -> Result<(usize, usize, Option<String>), ParseError> {
+    let invalid = |column: usize, reason: &str| {
+        ParseError::new(
+            line_no,
+            column,
+            ParseErrorKind::InvalidHeader(reason.to_owned()),
+        )
+    };
+
+    let (mut width, mut height, mut rule) = (None, None, None);
+    let (mut column, mut offset) = (1, 0);
+    for part in line.split(',') {
+        // point at the first non-whitespace character of this part when reporting errors
+        let part_column = column + part.chars().take_while(|c| c.is_whitespace()).count();
+        let part_offset = offset;
+        column += part.chars().count() + 1; // + 1 for the comma
+        offset += part.len() + 1;
+
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| invalid(part_column, "expected `key = value`"))?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "x" | "y" => {
+                let size = value
+                    .parse::<usize>()
+                    .map_err(|_| invalid(part_column, "size must be a whole number"))?;
+                if key == "x" {
+                    width = Some(size);
+                } else {
+                    height = Some(size);
+                }
+            }
+            "rule" => {
+                let value_offset = part_offset + part.find('=').unwrap() + 1;
+                rule = Some(line[value_offset..].trim().to_owned());
+                break;
+            }
+            _ => return Err(invalid(part_column, "unknown key (expected x, y or rule)")),
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(invalid(1, "both x and y must be given")),
+    }
+}
+
+/// Encode the given pattern as the contents of an RLE file.
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if let Some((x, y)) = pattern.origin {
+        out.push_str(&format!("#CXRLE Pos={},{}\n", x, y));
+    }
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        out.push_str(&format!("#O {}\n", author));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {}\n", comment));
+    }
+    out.push_str(&format!(
+        "x = {}, y = {}, rule = {}\n",
+        pattern.width,
+        pattern.height,
+        pattern.rule.as_deref().unwrap_or(DEFAULT_RULE)
+    ));
+
+    // walk the live cells in reading order, so that runs can be counted as we go
+    let mut live_cells = pattern.live_cells.clone();
+    live_cells.sort_unstable_by_key(|&(x, y)| (y, x));
+    live_cells.dedup();
+
+    let mut lines = LineWrapper::new(&mut out);
+    let (mut x, mut y) = (0, 0);
+    let mut alive_run = 0;
+    for (cell_x, cell_y) in live_cells {
+        let continues_run = alive_run > 0 && cell_y == y && cell_x == x + alive_run;
+        if continues_run {
+            alive_run += 1;
+            continue;
+        }
+        lines.push_run(alive_run, 'o');
+        x += alive_run;
+        alive_run = 1;
+
+        // trailing dead cells on a row are left implied, so just move on to the new row
+        if cell_y > y {
+            lines.push_run(cell_y - y, '$');
+            y = cell_y;
+            x = 0;
+        }
+        lines.push_run(cell_x - x, 'b');
+        x = cell_x;
+    }
+    lines.push_run(alive_run, 'o');
+    lines.push_run(1, '!');
+
+    out.push('\n');
+    out
+}
+
+/// Appends `<count><tag>` runs to a string, starting new lines as needed to keep them short.
+struct LineWrapper<'a> {
+    out: &'a mut String,
+    line_length: usize,
+}
+
+impl<'a> LineWrapper<'a> {
+    fn new(out: &'a mut String) -> LineWrapper<'a> {
+        LineWrapper {
+            out,
+            line_length: 0,
+        }
+    }
+
+    fn push_run(&mut self, count: usize, tag: char) {
+        let run = match count {
+            0 => return,
+            1 => tag.to_string(),
+            _ => format!("{}{}", count, tag),
+        };
+        // runs are never split across lines, so wrap before any run that wouldn't fit
+        if self.line_length + run.len() > MAX_LINE_LENGTH {
+            self.out.push('\n');
+            self.line_length = 0;
+        }
+        self.out.push_str(&run);
+        self.line_length += run.len();
+    }
+}
+
+#[cfg(test)]
+mod rle_test {
+    use super::{parse, write, MAX_LINE_LENGTH};
+    use crate::error::{ParseError, ParseErrorKind};
+    use crate::pattern::Pattern;
+    use crate::{GameOfLife, GameOfLifeSolution, Rule, Topology};
+    use std::path::PathBuf;
+
+    fn patterns_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("patterns")
+    }
+
+    #[test]
+    fn parses_glider() {
+        let pattern = parse("#C This is a glider.\nx = 3, y = 3\nbo$2bo$3o!").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.rule, None);
+        assert_eq!(
+            pattern.live_cells,
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn parses_multi_state_live_and_dead_cells() {
+        let pattern = parse("x = 3, y = 3, rule = B2/S/C3\n.A$2.A$3A!").unwrap();
+        assert_eq!(
+            pattern.live_cells,
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn parses_rule_with_commas() {
+        let pattern = parse("x = 1, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\no!").unwrap();
+        assert_eq!(pattern.rule.as_deref(), Some("R5,C0,M1,S34..58,B34..45,NM"));
+    }
+
+    #[test]
+    fn parses_rule_with_bounded_grid() {
+        let pattern = parse("x = 3, y = 3, rule = B3/S23:T100,80\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.rule.as_deref(), Some("B3/S23:T100,80"));
+        assert_eq!(pattern.parsed_rule(), Ok(Some(Rule::conway())));
+        let grid = pattern.bounded_grid().unwrap().unwrap();
+        assert_eq!(grid.topology, Topology::Torus);
+        assert_eq!((grid.width, grid.height), (100, 80));
+    }
+
+    #[test]
+    fn parses_rule_and_multi_line_runs_split_across_lines() {
+        let pattern = parse("x = 4, y = 5, rule = b3/s23\n2o\n2$b\n3o$\n!").unwrap();
+        assert_eq!(pattern.rule.as_deref(), Some("b3/s23"));
+        assert_eq!(
+            pattern.live_cells,
+            vec![(0, 0), (1, 0), (1, 2), (2, 2), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn parses_metadata_comments() {
+        let contents = "#N Breeder 1\n#O Bill Gosper\n#C The first pattern to be found that \
+            exhibits quadratic growth. Found\n#C  in the early 1970s.\n\
+            #CXRLE Pos=-7289,-1110 Gen=0\nx = 1, y = 1\no!";
+        let pattern = parse(contents).unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Breeder 1"));
+        assert_eq!(pattern.author.as_deref(), Some("Bill Gosper"));
+        assert_eq!(
+            pattern.comments,
+            vec![
+                "The first pattern to be found that exhibits quadratic growth. Found",
+                " in the early 1970s."
+            ]
+        );
+        assert_eq!(pattern.origin, Some((-7289, -1110)));
+    }
+
+    #[test]
+    fn reports_position_of_bad_cxrle_position() {
+        assert_eq!(
+            parse("#CXRLE Pos=-7289\nx = 1, y = 1\no!").unwrap_err(),
+            ParseError::new(
+                1,
+                12,
+                ParseErrorKind::InvalidNumber("\"-7289\" is not a valid position".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_metadata_when_written_out() {
+        let contents = std::fs::read_to_string(patterns_dir().join("lobster-breeder.txt")).unwrap();
+        let original = parse(&contents).unwrap();
+        let reloaded = parse(&write(&original)).unwrap();
+        assert_eq!(reloaded.name, original.name);
+        assert_eq!(reloaded.author, original.author);
+        assert_eq!(reloaded.comments, original.comments);
+        assert_eq!(reloaded.rule, original.rule);
+    }
+
+    #[test]
+    fn ignores_text_after_terminator() {
+        let pattern = parse("x = 1, y = 1\no! and then some notes\nmore notes").unwrap();
+        assert_eq!(pattern.live_cells, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn reports_missing_header() {
+        assert_eq!(
+            parse("#C nothing else\n").unwrap_err(),
+            ParseError::new(2, 1, ParseErrorKind::MissingHeader)
+        );
+        assert_eq!(
+            parse("#C no header\nbo$2bo$3o!").unwrap_err(),
+            ParseError::new(2, 1, ParseErrorKind::MissingHeader)
+        );
+    }
+
+    #[test]
+    fn reports_position_of_bad_header_value() {
+        let err = parse("x = 3, y = three\nbo$2bo$3o!").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 8));
+        assert!(matches!(err.kind, ParseErrorKind::InvalidHeader(_)));
+    }
+
+    #[test]
+    fn reports_position_of_unexpected_character() {
+        assert_eq!(
+            parse("x = 3, y = 3\nbo$2bo\n3z!").unwrap_err(),
+            ParseError::new(3, 2, ParseErrorKind::UnexpectedCharacter('z'))
+        );
+    }
+
+    #[test]
+    fn reports_position_of_dying_cells() {
+        assert_eq!(
+            parse("x = 3, y = 2, rule = B2/S/C3\nA.B$2pA!").unwrap_err(),
+            ParseError::new(2, 3, ParseErrorKind::DyingCell('B'))
+        );
+        assert_eq!(
+            parse("x = 3, y = 2, rule = B2/S/C3\nA$2pA!").unwrap_err(),
+            ParseError::new(2, 4, ParseErrorKind::DyingCell('p'))
+        );
+    }
+
+    #[test]
+    fn reports_zero_run_count() {
+        assert_eq!(
+            parse("x = 3, y = 3\n0o!").unwrap_err(),
+            ParseError::new(2, 2, ParseErrorKind::InvalidRunCount)
+        );
+    }
+
+    #[test]
+    fn reports_cells_outside_header_bounds() {
+        assert_eq!(
+            parse("x = 2, y = 2\n3o!").unwrap_err(),
+            ParseError::new(2, 2, ParseErrorKind::CellOutOfBounds { x: 2, y: 0 })
+        );
+        assert_eq!(
+            parse("x = 2, y = 2\n2$o!").unwrap_err(),
+            ParseError::new(2, 3, ParseErrorKind::CellOutOfBounds { x: 0, y: 2 })
+        );
+    }
+
+    #[test]
+    fn reports_missing_terminator() {
+        assert_eq!(
+            parse("x = 3, y = 3\nbo$2bo$3o\n").unwrap_err(),
+            ParseError::new(2, 10, ParseErrorKind::MissingTerminator)
+        );
+    }
+
+    #[test]
+    fn parses_every_bundled_pattern() {
+        for entry in std::fs::read_dir(patterns_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let pattern =
+                parse(&contents).unwrap_or_else(|e| panic!("failed to parse {:?}: {}", path, e));
+            assert!(
+                !pattern.live_cells.is_empty(),
+                "{:?} should have live cells",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn writes_glider() {
+        let mut game = GameOfLifeSolution::new(6, 6);
+        for &(x, y) in &[(3, 1), (4, 2), (2, 3), (3, 3), (4, 3)] {
+            game.toggle_cell(x, y);
+        }
+        assert_eq!(
+            write(&Pattern::from_game(&game)),
+            "#CXRLE Pos=2,1\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+    }
+
+    #[test]
+    fn writes_empty_board() {
+        let game = GameOfLifeSolution::new(4, 4);
+        let pattern = Pattern::from_game(&game);
+        assert_eq!(write(&pattern), "x = 0, y = 0, rule = B3/S23\n!\n");
+        assert!(parse(&write(&pattern)).unwrap().live_cells.is_empty());
+    }
+
+    #[test]
+    fn wraps_long_lines_between_runs() {
+        // a checkerboard row needs far more than one line's worth of runs
+        let pattern = Pattern {
+            width: 200,
+            height: 1,
+            rule: Some("B3/S23".to_owned()),
+            live_cells: (0..200).step_by(2).map(|x| (x, 0)).collect(),
+            ..Pattern::default()
+        };
+        let written = write(&pattern);
+        assert!(written.lines().count() > 2);
+        for line in written.lines() {
+            assert!(line.len() <= MAX_LINE_LENGTH, "line too long: {}", line);
+        }
+        assert_eq!(parse(&written).unwrap(), pattern);
+    }
+
+    #[test]
+    fn round_trips_every_bundled_pattern() {
+        for entry in std::fs::read_dir(patterns_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let original = parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            // straight from one pattern to the other, since some of the patterns (like clock.txt's
+            // 10016x6796 one) would take far longer to put on a board than to write out
+            let reloaded = parse(&write(&original)).unwrap();
+            assert_eq!(
+                (reloaded.width, reloaded.height, &reloaded.live_cells),
+                (original.width, original.height, &original.live_cells),
+                "{:?} changed after being written out and loaded back in",
+                path
+            );
+        }
+    }
+}
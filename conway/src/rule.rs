@@ -0,0 +1,445 @@
+use crate::hensel;
+use std::fmt;
+use std::str::FromStr;
+
+/// The rule a game runs by: how many live neighbours a dead cell needs to be born, and how many a
+/// live cell needs to survive. Conway's Game of Life is `B3/S23`, but there are plenty of other
+/// interesting ones, such as HighLife (`B36/S23`), Day & Night (`B3678/S34678`) and Seeds (`B2/S`).
+///
+/// Rules can also be "Generations" rules, where a live cell that doesn't survive isn't dead
+/// straight away, but instead decays through some number of dying states first. Dying cells
+/// don't count as live neighbours, and can't come back to life until they've finished dying.
+/// These are written with the total number of states on the end, e.g. Brian's Brain is
+/// `B2/S/C3`: cells are either dead, alive or dying.
+///
+/// Rules can also be "isotropic non-totalistic", where it matters which neighbours are alive and
+/// not just how many. These are written in Hensel notation, which adds letters after each number
+/// to say which arrangements of that many live neighbours count (or, after a `-`, which don't):
+/// e.g. `B2-a/S12` means cells are born with two live neighbours as long as those neighbours
+/// aren't next to each other. See the `hensel` module for more on the letters.
+///
+/// Rules can be parsed from B/S notation (`B3/S23`, `B2/S/C3`) or S/B notation (`S23/B3`, or the
+/// older `23/3` and `/2/3` without the letters), in any case. They're always displayed in B/S
+/// notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    // for each number of live neighbours, which Hensel letters (as bits; see `hensel`) cause a
+    // birth/survival
+    birth: [u16; 9],
+    survival: [u16; 9],
+    states: u8,
+}
+
+impl Rule {
+    /// Make a rule where dead cells with any of the `birth` numbers of live neighbours are born,
+    /// and live cells with any of the `survival` numbers of live neighbours survive.
+    ///
+    /// Panics if any of the numbers is more than 8, as no cell can have more neighbours than that.
+    pub fn new(birth: &[u32], survival: &[u32]) -> Rule {
+        let to_letters = |counts: &[u32]| {
+            let mut letters = [0; 9];
+            for &count in counts {
+                assert!(count <= 8, "a cell can't have {} neighbours", count);
+                letters[count as usize] = hensel::all_letters(count as usize);
+            }
+            letters
+        };
+        Rule {
+            birth: to_letters(birth),
+            survival: to_letters(survival),
+            states: 2,
+        }
+    }
+
+    /// The rule of Conway's Game of Life, `B3/S23`.
+    pub fn conway() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    /// Make this a Generations rule with the given number of states in total (including dead and
+    /// alive). Two states is an ordinary rule, where cells die immediately.
+    ///
+    /// Panics if there are fewer than two states.
+    pub fn with_states(self, states: u8) -> Rule {
+        assert!(
+            states >= 2,
+            "a rule needs at least a dead and an alive state"
+        );
+        Rule { states, ..self }
+    }
+
+    /// How many states cells can be in: 2 (dead or alive) for ordinary rules, or more for
+    /// Generations rules.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Does the rule only care how many live neighbours cells have, and not which ones are alive?
+    /// If not, `is_born` and friends can't say what happens; use `lookup_table` instead.
+    pub fn is_totalistic(&self) -> bool {
+        let all_or_nothing = |letters: &[u16; 9]| {
+            (0..=8).all(|count| letters[count] == 0 || letters[count] == hensel::all_letters(count))
+        };
+        all_or_nothing(&self.birth) && all_or_nothing(&self.survival)
+    }
+
+    /// Panic unless the rule is an ordinary two state rule, i.e. not a Generations rule with
+    /// dying states, which the given implementation can't run.
+    pub(crate) fn assert_two_state(&self, implementation: &str) {
+        assert_eq!(
+            self.states, 2,
+            "{} has dying states, which {} doesn't support; use GameOfLifeGenerations instead",
+            self, implementation
+        );
+    }
+
+    /// Panic unless the rule is totalistic, for implementations that only count neighbours rather
+    /// than looking at which ones are alive.
+    pub(crate) fn assert_totalistic(&self, implementation: &str) {
+        assert!(
+            self.is_totalistic(),
+            "{} isn't totalistic, which {} doesn't support",
+            self,
+            implementation
+        );
+    }
+
+    /// Panic unless the rule is both two state and totalistic.
+    pub(crate) fn assert_two_state_totalistic(&self, implementation: &str) {
+        self.assert_two_state(implementation);
+        self.assert_totalistic(implementation);
+    }
+
+    /// Does a dead cell with this many live neighbours come to life? Only meaningful for
+    /// totalistic rules.
+    pub fn is_born(&self, live_neighbours: u32) -> bool {
+        let count = live_neighbours as usize;
+        self.birth[count] == hensel::all_letters(count)
+    }
+
+    /// Does a live cell with this many live neighbours stay alive? Only meaningful for
+    /// totalistic rules.
+    pub fn survives(&self, live_neighbours: u32) -> bool {
+        let count = live_neighbours as usize;
+        self.survival[count] == hensel::all_letters(count)
+    }
+
+    /// Is a cell that's currently alive (or not) with this many live neighbours alive next tick?
+    ///
+    /// This ignores any dying states; see `next_generations_state` for Generations rules.
+    pub fn next_state(&self, alive: bool, live_neighbours: u32) -> bool {
+        if alive {
+            self.survives(live_neighbours)
+        } else {
+            self.is_born(live_neighbours)
+        }
+    }
+
+    /// What state a cell in the given state (0 for dead, 1 for alive, and 2 and up for dying)
+    /// with this many live neighbours is in next tick.
+    pub fn next_generations_state(&self, state: u8, live_neighbours: u32) -> u8 {
+        match state {
+            0 if self.is_born(live_neighbours) => 1,
+            0 => 0,
+            1 if self.survives(live_neighbours) => 1,
+            // a dying cell moves on to the next dying state, until it runs out of them
+            _ if state + 1 < self.states => state + 1,
+            _ => 0,
+        }
+    }
+
+    /// Work out what happens to a cell for every possible arrangement of it and its neighbours,
+    /// which works for any rule (and is faster to look things up in than working them out).
+    /// Ignores any dying states.
+    pub fn lookup_table(&self) -> RuleTable {
+        let mut next = [false; 512];
+        for (neighbourhood, next) in next.iter_mut().enumerate() {
+            // turn the neighbours into the order `hensel` expects them in
+            let neighbours = RuleTable::CLOCKWISE_BITS
+                .iter()
+                .enumerate()
+                .filter(|&(_, bit)| neighbourhood & 1 << bit != 0)
+                .fold(0u8, |neighbours, (i, _)| neighbours | 1 << i);
+            let (count, letter) = hensel::classify(neighbours);
+            let letters = if neighbourhood & RuleTable::CENTRE != 0 {
+                self.survival[count]
+            } else {
+                self.birth[count]
+            };
+            *next = letters & letter != 0;
+        }
+        RuleTable { next }
+    }
+}
+
+/// Whether a cell is alive next tick, for every possible 3x3 neighbourhood around it; see
+/// `Rule::lookup_table`.
+///
+/// The neighbourhood is given as 9 bits, going down each column of the 3x3 square from the left:
+/// bit 0 is the top left cell, bit 1 the left cell, bit 2 the bottom left cell, bit 3 the top
+/// cell, bit 4 the cell itself, and so on. That way the neighbourhood of the next cell to the
+/// right is just `neighbourhood >> 3` with the next column added to the top three bits.
+#[derive(Clone, Debug)]
+pub struct RuleTable {
+    next: [bool; 512],
+}
+
+impl RuleTable {
+    /// The bit for the cell itself in the neighbourhood.
+    pub const CENTRE: usize = 1 << 4;
+
+    // the bits of the neighbours going clockwise from the top, as `hensel` orders them
+    const CLOCKWISE_BITS: [usize; 8] = [3, 6, 7, 8, 5, 2, 1, 0];
+
+    /// Is the middle cell of the given neighbourhood alive next tick?
+    pub fn next_state(&self, neighbourhood: usize) -> bool {
+        self.next[neighbourhood]
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        write_conditions(f, &self.birth)?;
+        write!(f, "/S")?;
+        write_conditions(f, &self.survival)?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    /// Parse a rule in B/S notation (e.g. `B36/S23` or `B2/S/C3`) or S/B notation (e.g. `S23/B36`,
+    /// `23/36` or `/2/3`).
+    fn from_str(s: &str) -> Result<Rule, RuleParseError> {
+        let error =
+            |reason: &str| RuleParseError(format!("{:?} is not a valid rule: {}", s, reason));
+        let parts: Vec<&str> = s.trim().split('/').map(str::trim).collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(error("expected two or three parts separated by /"));
+        }
+
+        let (mut birth, mut survival, mut states) = (None, None, None);
+        for (i, part) in parts.into_iter().enumerate() {
+            let letter = part.chars().next().map(|c| c.to_ascii_uppercase());
+            let (which, value) = match letter {
+                Some(letter @ ('B' | 'S' | 'C')) => (letter, &part[1..]),
+                // without the letters, the parts are in survival/birth/states order
+                _ => (['S', 'B', 'C'][i], part),
+            };
+            let duplicate = match which {
+                'B' => birth.replace(value).is_some(),
+                'S' => survival.replace(value).is_some(),
+                _ => states.replace(value).is_some(),
+            };
+            if duplicate {
+                return Err(error("the same part is given twice"));
+            }
+        }
+
+        let conditions = |text: Option<&str>| {
+            parse_conditions(text.ok_or_else(|| error("expected both a B part and an S part"))?)
+                .ok_or_else(|| {
+                    error(
+                        "neighbour counts must be digits between 0 and 8, each optionally \
+                         followed by Hensel letters",
+                    )
+                })
+        };
+        let rule = Rule {
+            birth: conditions(birth)?,
+            survival: conditions(survival)?,
+            states: 2,
+        };
+        match states {
+            Some(states) => match states.parse() {
+                Ok(states) if states >= 2 => Ok(rule.with_states(states)),
+                _ => Err(error("the number of states must be between 2 and 255")),
+            },
+            None => Ok(rule),
+        }
+    }
+}
+
+/// Parse the conditions in one part of a rule, such as `23` or `2-a3ij`, into the Hensel letters
+/// they allow for each number of live neighbours. Returns `None` if they're malformed.
+fn parse_conditions(text: &str) -> Option<[u16; 9]> {
+    let mut conditions = [0; 9];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c.to_digit(10).filter(|&count| count <= 8)? as usize;
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut letters = 0;
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            letters |= hensel::letter_bit(count, letter.to_ascii_lowercase())?;
+        }
+        let all = hensel::all_letters(count);
+        conditions[count] |= match (negated, letters) {
+            // a number on its own means all arrangements of that many neighbours
+            (false, 0) => all,
+            (false, letters) => letters,
+            (true, 0) => return None,
+            (true, letters) => all & !letters,
+        };
+    }
+    Some(conditions)
+}
+
+/// Write out conditions in the form that `parse_conditions` reads, using whichever of the letters
+/// that are allowed or those that aren't is shorter.
+fn write_conditions(f: &mut fmt::Formatter, conditions: &[u16; 9]) -> fmt::Result {
+    for (count, &letters) in conditions.iter().enumerate() {
+        let all = hensel::all_letters(count);
+        if letters == 0 {
+            continue;
+        }
+        write!(f, "{}", count)?;
+        if letters != all {
+            let missing = all & !letters;
+            if missing.count_ones() < letters.count_ones() {
+                write!(f, "-")?;
+                hensel::letters(count, missing).try_for_each(|letter| write!(f, "{}", letter))?;
+            } else {
+                hensel::letters(count, letters).try_for_each(|letter| write!(f, "{}", letter))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The reason a rule couldn't be parsed from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleParseError(pub(crate) String);
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+#[cfg(test)]
+mod rule_test {
+    use super::Rule;
+
+    #[test]
+    fn parses_all_notations_in_any_case() {
+        let conway = Rule::conway();
+        for notation in &["B3/S23", "b3/s23", "S23/B3", "s23/b3", "23/3", " B3/S32 "] {
+            assert_eq!(notation.parse(), Ok(conway), "parsing {:?}", notation);
+        }
+    }
+
+    #[test]
+    fn parses_empty_parts() {
+        let seeds: Rule = "B2/S".parse().unwrap();
+        assert!(seeds.is_born(2));
+        assert!((0..=8).all(|count| !seeds.survives(count)));
+    }
+
+    #[test]
+    fn displays_in_canonical_form() {
+        let day_and_night: Rule = "s87643/b8763".parse().unwrap();
+        assert_eq!(day_and_night.to_string(), "B3678/S34678");
+        assert_eq!(Rule::default().to_string(), "B3/S23");
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for notation in &[
+            "",
+            "B3",
+            "B3/B6",
+            "B9/S23",
+            "B3/S2x",
+            "B3/S23/C1",
+            "B3/S23/C3/C3",
+        ] {
+            assert!(
+                notation.parse::<Rule>().is_err(),
+                "{:?} should not parse",
+                notation
+            );
+        }
+    }
+
+    #[test]
+    fn parses_generations_rules() {
+        let brians_brain = Rule::new(&[2], &[]).with_states(3);
+        for notation in &["B2/S/C3", "b2/s/c3", "/2/3", "S/B2/C3"] {
+            assert_eq!(notation.parse(), Ok(brians_brain), "parsing {:?}", notation);
+        }
+        assert_eq!(brians_brain.to_string(), "B2/S/C3");
+        // two states is just an ordinary rule
+        assert_eq!("B3/S23/C2".parse(), Ok(Rule::conway()));
+    }
+
+    #[test]
+    fn dying_cells_decay_until_dead() {
+        let rule = Rule::new(&[2], &[3]).with_states(4);
+        assert_eq!(rule.next_generations_state(0, 2), 1);
+        assert_eq!(rule.next_generations_state(1, 3), 1);
+        assert_eq!(rule.next_generations_state(1, 2), 2);
+        // dying cells ignore their neighbours entirely
+        assert_eq!(rule.next_generations_state(2, 2), 3);
+        assert_eq!(rule.next_generations_state(3, 3), 0);
+    }
+
+    #[test]
+    fn parses_hensel_notation() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.to_string(), "B2-a/S12");
+        assert_eq!("b2ceikn/s21".parse(), Ok(rule));
+        // listing every letter is the same as not listing any
+        assert_eq!("B3/S2cekain3".parse(), Ok(Rule::conway()));
+        assert_eq!(
+            "B3/S23-ck".parse::<Rule>().unwrap().to_string(),
+            "B3/S23-ck"
+        );
+        assert_eq!(
+            "B3/S23aeijkn".parse::<Rule>().unwrap().to_string(),
+            "B3/S23-cyqr"
+        );
+        for notation in &["B2x/S23", "B1k/S23", "B2-/S23", "B-a/S23"] {
+            assert!(notation.parse::<Rule>().is_err(), "{:?}", notation);
+        }
+    }
+
+    #[test]
+    fn lookup_table_matches_rule() {
+        let table = Rule::conway().lookup_table();
+        // a dead cell with the three cells above it alive is born
+        assert!(table.next_state(0b001_000_001 | 0b1000));
+        // but not with just two of them
+        assert!(!table.next_state(0b001_000_001));
+        // whereas a live one survives with two
+        assert!(table.next_state(0b001_010_001));
+
+        // with B2-a, two live neighbours cause a birth unless they're next to each other
+        let table = "B2-a/S".parse::<Rule>().unwrap().lookup_table();
+        let (top_left, top, bottom) = (1, 1 << 3, 1 << 5);
+        assert!(!table.next_state(top_left | top));
+        assert!(table.next_state(top | bottom));
+    }
+
+    #[test]
+    fn applies_birth_and_survival() {
+        let highlife = Rule::new(&[3, 6], &[2, 3]);
+        assert!(highlife.next_state(false, 6));
+        assert!(!highlife.next_state(true, 6));
+        assert!(highlife.next_state(true, 2));
+        assert!(!highlife.next_state(false, 2));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::game_of_life::{BoundingBox, GameOfLife};
+use std::collections::HashSet;
+use std::fmt;
+
+/// What a game looked like after its last tick, and how it got there: see `WithStats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// How many generations the game has been run for.
+    pub generation: u64,
+    /// How many cells are alive.
+    pub population: usize,
+    /// How many cells came to life in the last tick, if they're being counted (see
+    /// `WithStats::counting_births_and_deaths`) and the last step was a single tick.
+    pub births: Option<usize>,
+    /// How many live cells died (or started dying) in the last tick, like `births`.
+    pub deaths: Option<usize>,
+    /// The smallest box containing every live cell, or `None` if there aren't any.
+    pub bounding_box: Option<BoundingBox>,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "generation {}, population {}",
+            self.generation, self.population
+        )?;
+        if let (Some(births), Some(deaths)) = (self.births, self.deaths) {
+            write!(f, " (+{} -{})", births, deaths)?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps any GameOfLife to keep track of its `Stats`, which are updated after every `tick` or
+/// `tick_n`.
+///
+/// Only the generation, population and bounding box are kept track of to begin with, since the
+/// game can work those out however is quickest (e.g. without going through every cell of a
+/// HashLife pattern). Counting births and deaths means comparing every live cell before and after
+/// each tick, so that has to be asked for with `counting_births_and_deaths`, and even then it's
+/// skipped for `tick_n`, which can jump further than it's worth comparing across.
+///
+/// It's a GameOfLife itself, so it can be used wherever the game it wraps could be, and every
+/// method goes straight through to the wrapped game, so none of the game's own shortcuts are
+/// lost. Toggling cells doesn't change the stats until the next tick.
+#[derive(Clone, Debug)]
+pub struct WithStats<G> {
+    game: G,
+    stats: Stats,
+    count_births_and_deaths: bool,
+}
+
+impl<G: GameOfLife> WithStats<G> {
+    /// Start keeping track of the given game's stats, counting from generation 0.
+    pub fn new(game: G) -> WithStats<G> {
+        let stats = Stats {
+            generation: 0,
+            population: game.population(),
+            births: None,
+            deaths: None,
+            bounding_box: game.live_bounding_box(),
+        };
+        WithStats {
+            game,
+            stats,
+            count_births_and_deaths: false,
+        }
+    }
+
+    /// Count how many cells are born and die on each `tick`. That goes through every live cell
+    /// twice a tick, so it's best kept to games with a fixed size board.
+    pub fn counting_births_and_deaths(self) -> WithStats<G> {
+        WithStats {
+            count_births_and_deaths: true,
+            ..self
+        }
+    }
+
+    /// The stats as of the last tick (or as of when the game was wrapped, before any ticks).
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// The game being wrapped.
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    /// Stop keeping track of the stats, and get the game back.
+    pub fn into_inner(self) -> G {
+        self.game
+    }
+
+    /// Update the stats after the game has been run on by `generations`, leaving out births and
+    /// deaths.
+    fn record(&mut self, generations: u64) {
+        self.stats = Stats {
+            generation: self.stats.generation + generations,
+            population: self.game.population(),
+            births: None,
+            deaths: None,
+            bounding_box: self.game.live_bounding_box(),
+        };
+    }
+}
+
+impl<G: GameOfLife> GameOfLife for WithStats<G> {
+    fn is_cell_alive(&self, x: usize, y: usize) -> Option<bool> {
+        self.game.is_cell_alive(x, y)
+    }
+
+    fn cell_state(&self, x: usize, y: usize) -> Option<u8> {
+        self.game.cell_state(x, y)
+    }
+
+    fn num_states(&self) -> u8 {
+        self.game.num_states()
+    }
+
+    fn toggle_cell(&mut self, x: usize, y: usize) {
+        self.game.toggle_cell(x, y);
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.game.set_cell(x, y, alive);
+    }
+
+    fn clear(&mut self) {
+        self.game.clear();
+    }
+
+    fn population(&self) -> usize {
+        self.game.population()
+    }
+
+    fn live_cells(&self) -> Box<dyn Iterator<Item = (i64, i64)> + '_> {
+        self.game.live_cells()
+    }
+
+    fn tick(&mut self) {
+        if !self.count_births_and_deaths {
+            self.game.tick();
+            self.record(1);
+            return;
+        }
+        let before: HashSet<(i64, i64)> = self.game.live_cells().collect();
+        self.game.tick();
+        let after: HashSet<(i64, i64)> = self.game.live_cells().collect();
+        self.record(1);
+        self.stats.births = Some(after.difference(&before).count());
+        self.stats.deaths = Some(before.difference(&after).count());
+    }
+
+    fn tick_n(&mut self, n: u64) {
+        self.game.tick_n(n);
+        self.record(n);
+    }
+
+    fn width(&self) -> usize {
+        self.game.width()
+    }
+
+    fn height(&self) -> usize {
+        self.game.height()
+    }
+
+    fn tick_report(&self) -> Option<String> {
+        self.game.tick_report()
+    }
+
+    fn cell_state_at(&self, x: i64, y: i64) -> Option<u8> {
+        self.game.cell_state_at(x, y)
+    }
+
+    fn toggle_cell_at(&mut self, x: i64, y: i64) {
+        self.game.toggle_cell_at(x, y);
+    }
+
+    fn set_cell_at(&mut self, x: i64, y: i64, alive: bool) {
+        self.game.set_cell_at(x, y, alive);
+    }
+
+    fn live_bounding_box(&self) -> Option<BoundingBox> {
+        self.game.live_bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod stats_test {
+    use super::{Stats, WithStats};
+    use crate::game_of_life::{BoundingBox, GameOfLife};
+    use crate::game_of_life_generations::GameOfLifeGenerations;
+    use crate::game_of_life_hashlife::GameOfLifeHashLife;
+    use crate::game_of_life_solution::GameOfLifeSolution;
+
+    #[test]
+    fn counts_births_and_deaths() {
+        let mut game = WithStats::new(GameOfLifeSolution::new(5, 5)).counting_births_and_deaths();
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        // toggling doesn't count until the next tick
+        assert_eq!(game.stats().population, 0);
+        game.tick();
+        assert_eq!(
+            *game.stats(),
+            Stats {
+                generation: 1,
+                population: 3,
+                births: Some(2),
+                deaths: Some(2),
+                bounding_box: Some(BoundingBox {
+                    x: 2,
+                    y: 1,
+                    width: 1,
+                    height: 3
+                }),
+            }
+        );
+        assert_eq!(
+            game.stats().to_string(),
+            "generation 1, population 3 (+2 -2)"
+        );
+    }
+
+    #[test]
+    fn only_counts_births_and_deaths_when_asked() {
+        let mut game = WithStats::new(GameOfLifeSolution::new(5, 5));
+        for x in 1..4 {
+            game.toggle_cell(x, 2);
+        }
+        game.tick();
+        let stats = game.stats();
+        assert_eq!(
+            (stats.population, stats.births, stats.deaths),
+            (3, None, None)
+        );
+        assert_eq!(stats.to_string(), "generation 1, population 3");
+    }
+
+    #[test]
+    fn counts_jumps_as_one_step() {
+        // a glider, boxed up like the UI has it, on a board that goes on forever
+        let hashlife: Box<dyn GameOfLife> = Box::new(GameOfLifeHashLife::new(10, 10));
+        let mut game = WithStats::new(hashlife).counting_births_and_deaths();
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.toggle_cell(x, y);
+        }
+        game.tick_n(400);
+        let stats = game.stats();
+        assert_eq!(stats.generation, 400);
+        assert_eq!(stats.population, 5);
+        // there's no comparing cells across a jump
+        assert_eq!((stats.births, stats.deaths), (None, None));
+        assert_eq!(stats.bounding_box.map(|b| (b.x, b.y)), Some((100, 100)));
+        game.tick();
+        assert_eq!(game.stats().generation, 401);
+        // but single ticks still count them
+        assert_eq!(
+            (game.stats().births, game.stats().deaths),
+            (Some(2), Some(2))
+        );
+    }
+
+    #[test]
+    fn dying_cells_count_as_deaths() {
+        // under Brian's Brain, live cells always start dying the next tick
+        let game = GameOfLifeGenerations::new(5, 5).with_rule("B2/S/C3".parse().unwrap());
+        let mut game = WithStats::new(game).counting_births_and_deaths();
+        game.toggle_cell(1, 1);
+        game.toggle_cell(1, 2);
+        game.tick();
+        let stats = game.stats();
+        assert_eq!(
+            (stats.births, stats.deaths, stats.population),
+            (Some(4), Some(2), 4)
+        );
+    }
+}
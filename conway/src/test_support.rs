@@ -0,0 +1,69 @@
+//! Helpers shared by the tests of the different implementations, most of which check that they
+//! run just like `GameOfLifeSolution`.
+
+use crate::game_of_life::GameOfLife;
+use crate::game_of_life_solution::GameOfLifeSolution;
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// Every live cell on the board, row by row.
+pub(crate) fn live_cells(game: &dyn GameOfLife) -> Vec<(usize, usize)> {
+    (0..game.height())
+        .flat_map(|y| (0..game.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| game.is_cell_alive(x, y) == Some(true))
+        .collect()
+}
+
+/// Bring around a quarter of the cells on the board to life, scattered at random, but the same
+/// way every time for the same seed, so tests always check the same thing.
+pub(crate) fn scatter_cells(game: &mut dyn GameOfLife, seed: u32) {
+    let mut seed = seed;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            if seed >> 16 & 3 == 0 {
+                game.toggle_cell(x, y);
+            }
+        }
+    }
+}
+
+/// Tick the game alongside a `GameOfLifeSolution` that starts out with the same cells alive and
+/// runs by the given rule on the given topology, checking after every tick that they still match.
+///
+/// Every `poke_every` ticks (if given), a cell is toggled on both, like someone clicking in the
+/// UI, to check that changes made between ticks are picked up. `context` says which game this is
+/// when they don't match, e.g. how many threads it's using.
+pub(crate) fn assert_runs_like_the_solution(
+    game: &mut dyn GameOfLife,
+    rule: Rule,
+    topology: Topology,
+    ticks: usize,
+    poke_every: Option<usize>,
+    context: &str,
+) {
+    let mut solution = GameOfLifeSolution::new(game.width(), game.height())
+        .with_rule(rule)
+        .with_topology(topology);
+    for (x, y) in live_cells(game) {
+        solution.toggle_cell(x, y);
+    }
+    for generation in 0..ticks {
+        game.tick();
+        solution.tick();
+        assert_eq!(
+            live_cells(game),
+            live_cells(&solution),
+            "{}: {} on a {} at generation {}",
+            context,
+            rule,
+            topology,
+            generation
+        );
+        if poke_every.is_some_and(|every| generation % every == 0) {
+            let (x, y) = (generation % game.width(), generation % game.height());
+            game.toggle_cell(x, y);
+            solution.toggle_cell(x, y);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::rule::RuleParseError;
+use std::fmt;
+use std::str::FromStr;
+
+/// What happens at the edges of the board: whether cells past them are dead, or whether the
+/// edges join up with each other, so that e.g. a glider going off the right hand side comes back
+/// on at the left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// The board just stops at its edges, and cells past them are dead.
+    #[default]
+    Plane,
+    /// The left and right edges are joined, as are the top and bottom edges.
+    Torus,
+    /// A torus where one pair of edges is joined with a shift, so that crossing one of those
+    /// edges also moves you `shift` cells along it. For the top and bottom edges, going down off
+    /// the bottom moves cells to the right; for the left and right edges, going right off the
+    /// right hand side moves cells down.
+    TwistedTorus { edges: Edges, shift: isize },
+    /// A torus where one pair of edges is joined with a twist, so that crossing one of those
+    /// edges also mirrors you along it: e.g. with the top and bottom edges twisted, going off
+    /// the bottom left corner brings you back on at the top right.
+    KleinBottle { twisted: Edges },
+    /// Both pairs of edges are joined with a twist.
+    CrossSurface,
+}
+
+/// A pair of opposite edges of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Edges {
+    /// The top and bottom edges.
+    Horizontal,
+    /// The left and right edges.
+    Vertical,
+}
+
+impl Topology {
+    /// Find the cell on a `width` by `height` board at `x`, `y`, where those can be off the edge
+    /// of the board (in which case they're wrapped around onto it as the topology says).
+    /// Returns `None` if the cell is off the edge of a plane, and so always dead.
+    pub fn wrap(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        if x >= 0 && y >= 0 && x < w && y < h {
+            return Some((x as usize, y as usize));
+        }
+        // how many times we have to cross each pair of edges to get back onto the board
+        let (crossed_vertical, crossed_horizontal) = (x.div_euclid(w), y.div_euclid(h));
+        let mirror_if = |odd_crossings: isize, n: isize, size: isize| {
+            if odd_crossings.rem_euclid(2) == 1 {
+                size - 1 - n
+            } else {
+                n
+            }
+        };
+        let (x, y) = match self {
+            Topology::Plane => return None,
+            Topology::Torus => (x, y),
+            Topology::TwistedTorus {
+                edges: Edges::Horizontal,
+                shift,
+            } => (x + crossed_horizontal * shift, y),
+            Topology::TwistedTorus {
+                edges: Edges::Vertical,
+                shift,
+            } => (x, y + crossed_vertical * shift),
+            Topology::KleinBottle {
+                twisted: Edges::Horizontal,
+            } => (mirror_if(crossed_horizontal, x.rem_euclid(w), w), y),
+            Topology::KleinBottle {
+                twisted: Edges::Vertical,
+            } => (x, mirror_if(crossed_vertical, y.rem_euclid(h), h)),
+            Topology::CrossSurface => (
+                mirror_if(crossed_horizontal, x.rem_euclid(w), w),
+                mirror_if(crossed_vertical, y.rem_euclid(h), h),
+            ),
+        };
+        Some((x.rem_euclid(w) as usize, y.rem_euclid(h) as usize))
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let edges = |edges: &Edges| match edges {
+            Edges::Horizontal => "top and bottom",
+            Edges::Vertical => "left and right",
+        };
+        match self {
+            Topology::Plane => write!(f, "plane"),
+            Topology::Torus => write!(f, "torus"),
+            Topology::TwistedTorus { edges: e, shift } => write!(
+                f,
+                "twisted torus (shifted by {} across the {} edges)",
+                shift,
+                edges(e)
+            ),
+            Topology::KleinBottle { twisted } => {
+                write!(f, "Klein bottle (twisted {} edges)", edges(twisted))
+            }
+            Topology::CrossSurface => write!(f, "cross-surface"),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = RuleParseError;
+
+    /// Parse one of `plane`, `torus`, `twisted-torus:<shift>` (shifted across the top and bottom
+    /// edges), `klein-bottle` (with the top and bottom edges twisted) or `cross-surface`, in any
+    /// case. For other variations, use a bounded grid in a rule instead (see `BoundedGrid`).
+    fn from_str(s: &str) -> Result<Topology, RuleParseError> {
+        let s = s.trim().to_ascii_lowercase();
+        let topology = match s.as_str() {
+            "plane" => Topology::Plane,
+            "torus" => Topology::Torus,
+            "klein-bottle" => Topology::KleinBottle {
+                twisted: Edges::Horizontal,
+            },
+            "cross-surface" => Topology::CrossSurface,
+            _ => {
+                let shift = s
+                    .strip_prefix("twisted-torus:")
+                    .and_then(|shift| shift.parse().ok())
+                    .ok_or_else(|| {
+                        RuleParseError(format!(
+                            "{:?} is not a topology: expected plane, torus, twisted-torus:<shift>, \
+                             klein-bottle or cross-surface",
+                            s
+                        ))
+                    })?;
+                Topology::TwistedTorus {
+                    edges: Edges::Horizontal,
+                    shift,
+                }
+            }
+        };
+        Ok(topology)
+    }
+}
+
+/// A board size and topology, as given by the suffix Golly puts on the end of a rule for bounded
+/// grids, such as the `:T100,80` in `B3/S23:T100,80` for a 100 by 80 torus.
+///
+/// The suffix starts with `P` for a plane, `T` for a torus, `K` for a Klein bottle or `C` for a
+/// cross-surface, followed by the width and height. A torus can have a shift after one of those,
+/// as in `T100+5,80` to shift the top and bottom edges by 5 (see `Topology::TwistedTorus`), and a
+/// Klein bottle has a `*` after the dimension whose edges are twisted, as in `K100*,80`. If the
+/// height is left out, the grid is square. A size of 0 means that the grid is unbounded in that
+/// direction, which is left to whatever size the board is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BoundedGrid {
+    pub topology: Topology,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BoundedGrid {
+    /// Split the bounded grid suffix (if any) off the end of a rule, returning the rule without it
+    /// and the grid it described.
+    pub fn split_from_rule(rule: &str) -> Result<(&str, Option<BoundedGrid>), RuleParseError> {
+        match rule.split_once(':') {
+            Some((rule, grid)) => Ok((rule.trim(), Some(grid.parse()?))),
+            None => Ok((rule.trim(), None)),
+        }
+    }
+}
+
+impl FromStr for BoundedGrid {
+    type Err = RuleParseError;
+
+    /// Parse the part of a bounded grid suffix after the `:`, such as `T100,80`.
+    fn from_str(s: &str) -> Result<BoundedGrid, RuleParseError> {
+        let error = |reason: &str| {
+            RuleParseError(format!("{:?} is not a valid bounded grid: {}", s, reason))
+        };
+        let s = s.trim();
+        let kind = s
+            .chars()
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .ok_or_else(|| error("it's empty"))?;
+        let dimensions = &s[kind.len_utf8()..];
+        let (width, height) = dimensions
+            .split_once(',')
+            .unwrap_or((dimensions, dimensions));
+
+        // each dimension is a number, optionally followed by a shift or a twist
+        let dimension = |text: &str| {
+            let text = text.trim();
+            let (text, twisted) = match text.strip_suffix('*') {
+                Some(text) => (text, true),
+                None => (text, false),
+            };
+            let (size, shift) = match text.find(['+', '-']) {
+                Some(index) => (&text[..index], Some(&text[index..])),
+                None => (text, None),
+            };
+            let size = size
+                .parse::<usize>()
+                .map_err(|_| error("sizes must be whole numbers"))?;
+            let shift = shift
+                .map(|shift| shift.trim_start_matches('+').parse::<isize>())
+                .transpose()
+                .map_err(|_| error("shifts must be whole numbers"))?;
+            Ok::<_, RuleParseError>((size, shift, twisted))
+        };
+        let (width, width_shift, width_twisted) = dimension(width)?;
+        let (height, height_shift, height_twisted) = dimension(height)?;
+
+        let has_shift = width_shift.is_some() || height_shift.is_some();
+        let has_twist = width_twisted || height_twisted;
+        if has_shift && kind != 'T' {
+            return Err(error("only a torus can have a shift"));
+        }
+        if has_twist && kind != 'K' {
+            return Err(error(
+                "only a Klein bottle can have a twisted pair of edges",
+            ));
+        }
+        // the top and bottom edges are as long as the board is wide, so the shift or twist for
+        // them goes with the width
+        let edges = |on_width: bool, on_height: bool| match (on_width, on_height) {
+            (true, true) => Err(error("only one pair of edges can be shifted or twisted")),
+            (false, true) => Ok(Edges::Vertical),
+            _ => Ok(Edges::Horizontal),
+        };
+
+        let topology = match kind {
+            'P' => Topology::Plane,
+            'T' if has_shift => Topology::TwistedTorus {
+                edges: edges(width_shift.is_some(), height_shift.is_some())?,
+                shift: width_shift.or(height_shift).unwrap(),
+            },
+            'T' => Topology::Torus,
+            'K' => Topology::KleinBottle {
+                twisted: edges(width_twisted, height_twisted)?,
+            },
+            'C' => Topology::CrossSurface,
+            _ => {
+                return Err(error(
+                    "expected P (plane), T (torus), K (Klein bottle) or C (cross-surface)",
+                ))
+            }
+        };
+        Ok(BoundedGrid {
+            topology,
+            width,
+            height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod topology_test {
+    use super::{BoundedGrid, Edges, Topology};
+
+    #[test]
+    fn plane_has_nothing_past_the_edges() {
+        assert_eq!(Topology::Plane.wrap(2, 3, 5, 5), Some((2, 3)));
+        assert_eq!(Topology::Plane.wrap(-1, 3, 5, 5), None);
+        assert_eq!(Topology::Plane.wrap(2, 5, 5, 5), None);
+    }
+
+    #[test]
+    fn torus_wraps_both_ways() {
+        assert_eq!(Topology::Torus.wrap(-1, -1, 5, 4), Some((4, 3)));
+        assert_eq!(Topology::Torus.wrap(5, 4, 5, 4), Some((0, 0)));
+        // even when going around more than once
+        assert_eq!(Topology::Torus.wrap(12, -9, 5, 4), Some((2, 3)));
+    }
+
+    #[test]
+    fn twisted_torus_shifts_along_edges() {
+        let shifted = Topology::TwistedTorus {
+            edges: Edges::Horizontal,
+            shift: 2,
+        };
+        assert_eq!(shifted.wrap(1, 4, 5, 4), Some((3, 0)));
+        assert_eq!(shifted.wrap(1, -1, 5, 4), Some((4, 3)));
+        // the other pair of edges is joined as normal
+        assert_eq!(shifted.wrap(5, 1, 5, 4), Some((0, 1)));
+    }
+
+    #[test]
+    fn klein_bottle_and_cross_surface_mirror_across_twisted_edges() {
+        let klein = Topology::KleinBottle {
+            twisted: Edges::Horizontal,
+        };
+        assert_eq!(klein.wrap(0, 4, 5, 4), Some((4, 0)));
+        assert_eq!(klein.wrap(1, -1, 5, 4), Some((3, 3)));
+        assert_eq!(klein.wrap(-1, 1, 5, 4), Some((4, 1)));
+
+        assert_eq!(Topology::CrossSurface.wrap(-1, 1, 5, 4), Some((4, 2)));
+        assert_eq!(Topology::CrossSurface.wrap(1, 4, 5, 4), Some((3, 0)));
+    }
+
+    #[test]
+    fn parses_golly_bounded_grids() {
+        let grid = |text: &str| text.parse::<BoundedGrid>().unwrap();
+        assert_eq!(
+            grid("T100,80"),
+            BoundedGrid {
+                topology: Topology::Torus,
+                width: 100,
+                height: 80,
+            }
+        );
+        assert_eq!(
+            grid("T30+5,20").topology,
+            Topology::TwistedTorus {
+                edges: Edges::Horizontal,
+                shift: 5,
+            }
+        );
+        assert_eq!(
+            grid("t30,20-3").topology,
+            Topology::TwistedTorus {
+                edges: Edges::Vertical,
+                shift: -3,
+            }
+        );
+        assert_eq!(
+            grid("K30,20*").topology,
+            Topology::KleinBottle {
+                twisted: Edges::Vertical,
+            }
+        );
+        assert_eq!(grid("C40").height, 40);
+        assert_eq!(grid("P0,0").topology, Topology::Plane);
+        for invalid in &["", "X10,10", "P10+1,10", "T10+1,10+1", "C10*,10", "Tx,10"] {
+            assert!(invalid.parse::<BoundedGrid>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn splits_grid_off_rules() {
+        let (rule, grid) = BoundedGrid::split_from_rule("B3/S23:T100,80").unwrap();
+        assert_eq!(rule, "B3/S23");
+        assert_eq!(grid.unwrap().width, 100);
+        assert_eq!(
+            BoundedGrid::split_from_rule("B3/S23").unwrap(),
+            ("B3/S23", None)
+        );
+    }
+
+    #[test]
+    fn parses_command_line_values() {
+        assert_eq!("Torus".parse(), Ok(Topology::Torus));
+        assert_eq!(
+            "twisted-torus:-2".parse(),
+            Ok(Topology::TwistedTorus {
+                edges: Edges::Horizontal,
+                shift: -2,
+            })
+        );
+        assert!("doughnut".parse::<Topology>().is_err());
+    }
+}
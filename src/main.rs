@@ -1,4 +1,5 @@
 // import some types and macros we want to use
+use conway::GameOfLife;
 use std::path::PathBuf;
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
@@ -22,6 +23,16 @@ arg_enum! {
         Broken,
         Solution,
         Mine,
+        Generations,
+        Isotropic,
+        LargerThanLife,
+        Unbounded,
+        HashLife,
+        BitPacked,
+        Parallel,
+        Tiled,
+        Incremental,
+        Simd,
     }
 }
 
@@ -42,22 +53,75 @@ struct Opt {
     #[structopt(possible_values = &Implementation::variants(), case_insensitive = true)]
     implementation: Implementation,
 
-    /// What pattern file should the renderer load as a starting file? If not specified, a default
+    /// What pattern file should the renderer load as a starting file? RLE, plaintext (.cells), Life
+    /// 1.05/1.06 (.lif) and Macrocell (.mc) files are supported. If not specified, a default
     /// pattern generator will be used.
     #[structopt(parse(from_os_str))]
     pattern: Option<PathBuf>,
 
+    /// Where to put the top left of the pattern on the board, as `x,y` (e.g. `--at 10,5`).
+    /// Defaults to the top left of the board.
+    #[structopt(long, allow_hyphen_values = true)]
+    at: Option<conway::Position>,
+
+    /// Put the pattern in the middle of the board, instead of at the top left.
+    #[structopt(long, conflicts_with = "at")]
+    center: bool,
+
+    /// How many degrees to turn the pattern clockwise: 0, 90, 180 or 270.
+    #[structopt(long, default_value = "0")]
+    rotate: conway::Rotation,
+
+    /// Mirror the pattern before rotating it: `horizontal` (left to right) or `vertical` (top to
+    /// bottom).
+    #[structopt(long, default_value = "none")]
+    flip: conway::Flip,
+
+    /// Which rule to run the game with, in B/S notation (e.g. `B36/S23` for HighLife), or in
+    /// Golly's notation (e.g. `R5,C0,M1,S34..58,B34..45,NM`) for the largerthanlife
+    /// implementation. Defaults to the rule given in the pattern file, or Conway's Game of Life if
+    /// there isn't one. Like in Golly, the rule can end with a bounded grid such as `:T100,80`
+    /// for a 100x80 torus.
+    #[structopt(long)]
+    rule: Option<String>,
+
+    /// What happens at the edges of the board: `plane` (cells past them are dead), `torus`
+    /// (opposite edges are joined), `twisted-torus:<shift>` (like a torus, but crossing the top
+    /// or bottom edge shifts cells along), `klein-bottle` or `cross-surface`. Defaults to the
+    /// topology of the bounded grid in the rule, or a plane if there isn't one.
+    #[structopt(long)]
+    topology: Option<conway::Topology>,
+
+    /// How many threads the parallel implementation should split each tick between. Defaults to
+    /// as many as the computer can run at once.
+    #[structopt(long)]
+    threads: Option<usize>,
+
     /// How many game ticks per second should we aim for?
     #[structopt(long, default_value = "2")]
     fps: u8,
 
-    /// Width of the game board (you should stick to 300 or less probably). The default gives a nice output with the default pattern generator.
-    #[structopt(long, short, default_value = "49")]
-    width: usize,
+    /// Width of the game board (you should stick to 300 or less probably). Defaults to the width
+    /// of the bounded grid in the rule if there is one, or else 49, which gives a nice output
+    /// with the default pattern generator.
+    #[structopt(long, short)]
+    width: Option<usize>,
 
-    /// Height of the game board (you should stick to 300 or less probably). The default gives a nice output with the default pattern generator.
-    #[structopt(long, short, default_value = "40")]
-    height: usize,
+    /// Height of the game board (you should stick to 300 or less probably). Defaults to the height
+    /// of the bounded grid in the rule if there is one, or else 40, which gives a nice output
+    /// with the default pattern generator.
+    #[structopt(long, short)]
+    height: Option<usize>,
+
+    /// Size the board to fit the pattern (plus some padding) instead of using --width and
+    /// --height. The pattern is centered on the board unless --at is given.
+    #[structopt(long, requires = "pattern", conflicts_with_all = &["width", "height"])]
+    auto_size: bool,
+
+    /// How many empty cells to leave around each side of the pattern when using --auto-size (or
+    /// just to the right of and below it, when --at says where it goes).
+    #[structopt(long, default_value = "5")]
+    padding: usize,
 
     /// How big (in pixels) should each cell be? Value must be between 1 and 32 (inclusive).
     #[structopt(long, default_value = "16")]
@@ -66,6 +130,37 @@ struct Opt {
     /// Should the simulation start paused?
     #[structopt(long)]
     paused: bool,
+
+    /// Run the game this many generations ahead before showing it.
+    #[structopt(long)]
+    skip_to: Option<u64>,
+
+    /// How many generations to jump ahead by when J is pressed.
+    #[structopt(long, default_value = "1000")]
+    jump: u64,
+
+    /// Run the game without the UI, for --generations generations, then print its stats and
+    /// whether it's settled into repeating itself.
+    #[structopt(long)]
+    headless: bool,
+
+    /// How many generations to run for with --headless.
+    // no `requires = "headless"` here, since a default value counts as being given
+    #[structopt(long, default_value = "1000")]
+    generations: u64,
+
+    /// With --headless, run until the board settles down (apart from any gliders flying away)
+    /// instead of for a set number of generations, then report when it did.
+    #[structopt(long, requires = "headless")]
+    until_stable: bool,
+
+    /// The most generations to run for with --until-stable, before giving up.
+    #[structopt(long, default_value = "100000")]
+    max_generations: u64,
+
+    /// How often to report how it's going with --until-stable, in generations.
+    #[structopt(long, default_value = "1000")]
+    progress_every: u64,
 }
 
 pub fn main() {
@@ -86,6 +181,111 @@ pub fn main() {
         panic!("Invalid cell size provided - must be between 1 and 32, inclusive")
     }
 
+    // load the pattern (if any) before building the game, so that we find out about problems
+    // with the file (e.g. it being malformed) straight away; if there are any, report them and
+    // bail out
+    //
+    // Macrocell files can hold patterns far too big to list cell by cell, but HashLife is a
+    // quadtree just like they are, so it can load them straight from the file; in that case only
+    // the pattern's size and details are read here, and its contents are kept for later (unless
+    // it's being turned around, which HashLife can't do to a whole quadtree)
+    let mut macrocell_contents = None;
+    let pattern = config.pattern.as_ref().map(|pattern_filename| {
+        let loaded = std::fs::read_to_string(pattern_filename)
+            .map_err(conway::PatternError::from)
+            .and_then(|contents| {
+                let format = conway::PatternFormat::detect(pattern_filename, &contents)?;
+                if format == conway::PatternFormat::Macrocell
+                    && matches!(config.implementation, Implementation::HashLife)
+                    && config.rotate == conway::Rotation::None
+                    && config.flip == conway::Flip::None
+                {
+                    let pattern = conway::Pattern::parse_without_cells(&contents, format)?;
+                    macrocell_contents = Some(contents);
+                    Ok(pattern)
+                } else {
+                    Ok(conway::Pattern::parse(&contents, format)?)
+                }
+            });
+        match loaded {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                eprintln!("Failed to load pattern from {:?}: {}", pattern_filename, e);
+                std::process::exit(1);
+            }
+        }
+    });
+    if let Some(pattern) = &pattern {
+        print_pattern_details(pattern);
+    }
+
+    // a rule given on the command line wins over the pattern's own rule
+    let rule = config
+        .rule
+        .as_deref()
+        .or_else(|| pattern.as_ref().and_then(|p| p.rule.as_deref()));
+
+    // split off the bounded grid (e.g. the `:T100,80` in `B3/S23:T100,80`) from the end of the
+    // rule, if there is one, since the rule itself is parsed differently depending on the
+    // implementation
+    let (rule, grid) = match rule.map(conway::BoundedGrid::split_from_rule).transpose() {
+        Ok(Some((rule, grid))) => (Some(rule), grid),
+        Ok(None) => (None, None),
+        Err(e) => {
+            eprintln!("Can't run the rule: {} (try passing --rule)", e);
+            std::process::exit(1);
+        }
+    };
+    let topology = config
+        .topology
+        .or_else(|| grid.map(|grid| grid.topology))
+        .unwrap_or_default();
+    println!("Running on a {}", topology);
+
+    let placement = conway::Placement {
+        position: if config.center || (config.auto_size && config.at.is_none()) {
+            conway::Position::Centered
+        } else {
+            config.at.unwrap_or_default()
+        },
+        rotation: config.rotate,
+        flip: config.flip,
+    };
+
+    // when auto-sizing, make the board as big as the pattern (once it's been turned around) plus
+    // the padding on each side, or if it's been put somewhere in particular, big enough to reach
+    // there plus the padding after it; `--auto-size` requires a pattern, so the `unwrap()` can't
+    // fail
+    let (width, height) = if config.auto_size {
+        let placed = pattern
+            .as_ref()
+            .unwrap()
+            .transformed(placement.rotation, placement.flip);
+        let (x, y, padding) = match placement.position {
+            conway::Position::Offset(x, y) => {
+                (x.max(0) as usize, y.max(0) as usize, config.padding)
+            }
+            conway::Position::Centered => (0, 0, 2 * config.padding),
+        };
+        (x + placed.width + padding, y + placed.height + padding)
+    } else {
+        // a size of 0 in a bounded grid means it's unbounded that way, which is as good as
+        // not saying
+        let grid_size = |size: fn(&conway::BoundedGrid) -> usize| {
+            grid.as_ref().map(size).filter(|&size| size > 0)
+        };
+        (
+            config
+                .width
+                .or_else(|| grid_size(|grid| grid.width))
+                .unwrap_or(49),
+            config
+                .height
+                .or_else(|| grid_size(|grid| grid.height))
+                .unwrap_or(40),
+        )
+    };
+
     // pattern match on the chosen implementation name to find the correct implementation, and store
     // that in a Box. Putting something in a Box basically means "move it from the stack to the
     // heap". We need to do this because we have multiple implementations of a trait (which we want
@@ -95,11 +295,131 @@ pub fn main() {
     // can't know at compile time how much space to reserve in the stack for this amorphous blob
     // of possibilities, so consequently you always have to store Trait Objects in the heap.
     let mut game: Box<dyn conway::GameOfLife> = match config.implementation {
-        Implementation::Broken => {
-            Box::new(conway::GameOfLiveBroken::new(config.width, config.height))
+        // these implementations don't have edges that can be joined up, so they only run on a
+        // plane (for the unbounded implementation, one that goes on forever)
+        Implementation::Broken | Implementation::Unbounded | Implementation::HashLife
+            if topology != conway::Topology::Plane =>
+        {
+            eprintln!(
+                "The {} implementation can only run on a plane",
+                config.implementation
+            );
+            std::process::exit(1);
         }
+        Implementation::Broken => Box::new(conway::GameOfLiveBroken::new(width, height)),
         Implementation::Solution => {
-            Box::new(conway::GameOfLifeSolution::new(config.width, config.height))
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            Box::new(
+                conway::GameOfLifeSolution::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::Generations => {
+            let rule = parse_rule(config.implementation, rule, conway::Rule::is_totalistic);
+            Box::new(
+                conway::GameOfLifeGenerations::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::Isotropic => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2
+            });
+            Box::new(
+                conway::GameOfLifeIsotropic::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::LargerThanLife => {
+            let rule = parse_rule(config.implementation, rule, |_| true);
+            Box::new(
+                conway::GameOfLifeLargerThanLife::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::BitPacked => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            Box::new(
+                conway::GameOfLifeBitPacked::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::Parallel => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            let mut game = conway::GameOfLifeParallel::new(width, height)
+                .with_rule(rule)
+                .with_topology(topology);
+            if let Some(threads) = config.threads {
+                game = game.with_threads(threads.max(1));
+            }
+            println!("Splitting each tick between {} threads", game.threads());
+            Box::new(game)
+        }
+        Implementation::Tiled => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            Box::new(
+                conway::GameOfLifeTiled::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        Implementation::Incremental => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            Box::new(
+                conway::GameOfLifeIncremental::new(width, height)
+                    .with_rule(rule)
+                    .with_topology(topology),
+            )
+        }
+        #[cfg(feature = "simd")]
+        Implementation::Simd => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic()
+            });
+            let game = conway::GameOfLifeSimd::new(width, height)
+                .with_rule(rule)
+                .with_topology(topology);
+            println!("Adding up neighbours with {}", game.instruction_set());
+            Box::new(game)
+        }
+        // the SIMD implementation is behind a cargo feature, since it's full of unsafe code
+        #[cfg(not(feature = "simd"))]
+        Implementation::Simd => {
+            eprintln!("The simd implementation needs building with `--features simd`");
+            std::process::exit(1);
+        }
+        Implementation::Unbounded => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && rule.is_totalistic() && !rule.is_born(0)
+            });
+            Box::new(conway::GameOfLifeUnbounded::new(width, height).with_rule(rule))
+        }
+        Implementation::HashLife => {
+            let rule = parse_rule(config.implementation, rule, |rule: &conway::Rule| {
+                rule.states() == 2 && !rule.is_born(0)
+            });
+            let mut game = conway::GameOfLifeHashLife::new(width, height).with_rule(rule);
+            if let (Some(contents), Some(pattern)) = (&macrocell_contents, &pattern) {
+                let (x, y) = pattern.offset_for(&game, placement.position);
+                game.load_macrocell(contents, x, y)
+                    .expect("the Macrocell file was already read through once");
+            }
+            Box::new(game)
         }
         Implementation::Mine => {
             //TODO reference your implementation here for the Part 1 exercise :)
@@ -118,23 +438,190 @@ conway::GameOfLifeMine - good luck!",
     // here we combine pattern matching with an if statement; if the pattern on the left (just after
     // `if let`) matches, then the condition evaluates to true, and any free variables in the
     // pattern are bound to the relevant values on the right hand side.
-    if let Some(pattern) = config.pattern {
+    if macrocell_contents.is_some() {
+        // HashLife has already loaded it, above
+    } else if let Some(pattern) = &pattern {
         // `a.as_mut()` is basically the same as doing `&mut a`, but in this case our `a` is in a
         // Box which we would need to dereference first (by prepending it with `*`), so we use
         // `as_mut()` because it's a bit less "noisy" than writing out `&mut *a`; when you call a
         // function on an object, Rust "auto-dereferences" for your convenience, so using `as_mut()`
         // will handle the dereference for us.
-        conway::load_and_apply_pattern(game.as_mut(), &pattern);
+        if let Err(e) = pattern.check_fits(game.as_ref(), &placement) {
+            eprintln!("{} (try a bigger --width/--height, or --auto-size)", e);
+            std::process::exit(1);
+        }
+        pattern.apply_with(game.as_mut(), &placement);
     } else {
         conway::apply_default_pattern(game.as_mut());
     }
 
+    // keep track of which generation the game is on and how it's going, counting any skipped
+    // generations; counting births and deaths means comparing every live cell each tick, which
+    // would hold up the implementations whose patterns can grow as big as they like
+    let mut game = conway::WithStats::new(game);
+    if !matches!(
+        config.implementation,
+        Implementation::Unbounded | Implementation::HashLife
+    ) {
+        game = game.counting_births_and_deaths();
+    }
+    if let Some(generation) = config.skip_to {
+        let start = std::time::Instant::now();
+        game.tick_n(generation);
+        println!(
+            "Skipped to generation {} in {:?}",
+            generation,
+            start.elapsed()
+        );
+    }
+
+    if config.headless {
+        if config.until_stable {
+            run_until_stable(game, config.max_generations, config.progress_every);
+        } else {
+            run_headless(game, config.generations);
+        }
+        return;
+    }
+
     ui::run_game(
         game,
         &ui::UiOptions {
             millis_between_ticks: (1000.0 / (config.fps as f32)) as u64,
             square_size: config.cell_size,
             start_paused: config.paused,
+            title: match pattern.and_then(|p| p.name) {
+                Some(name) => format!("RustLife - {}", name),
+                None => "RustLife".to_owned(),
+            },
+            jump_generations: config.jump,
         },
     )
 }
+
+/// Run the game for the given number of generations without showing it, then report how it went,
+/// including the period it settled into (if it did), or the speed it flies at if it's a spaceship.
+fn run_headless(mut game: conway::WithStats<Box<dyn conway::GameOfLife>>, generations: u64) {
+    let mut period_detector = conway::PeriodDetector::new();
+    period_detector.observe(&game, game.stats().generation);
+    let mut spaceship_detector = conway::SpaceshipDetector::new();
+    spaceship_detector.observe(&game, game.stats().generation);
+    let start = std::time::Instant::now();
+    for _ in 0..generations {
+        game.tick();
+        let generation = game.stats().generation;
+        let already_periodic = period_detector.periodicity().is_some();
+        if let Some(periodicity) = period_detector.observe(&game, generation) {
+            if !already_periodic {
+                println!("Generation {}: board is now {}", generation, periodicity);
+            }
+        }
+        let already_spaceship = spaceship_detector.spaceship().is_some();
+        if let Some(spaceship) = spaceship_detector.observe(&game, generation) {
+            if !already_spaceship {
+                println!("Generation {}: board is {}", generation, spaceship);
+            }
+        }
+    }
+    println!("Ran {} generations in {:?}", generations, start.elapsed());
+    println!("Finished at {}", game.stats());
+    match (
+        period_detector.periodicity(),
+        spaceship_detector.spaceship(),
+    ) {
+        (Some(periodicity), _) => println!("The board is {}", periodicity),
+        (None, Some(spaceship)) => println!("The board is {}", spaceship),
+        (None, None) if game.population() > conway::MAX_OBSERVED_POPULATION => println!(
+            "The board has too many live cells ({}) to keep track of whether it's repeating itself",
+            game.population()
+        ),
+        (None, None) => println!("The board hasn't settled into repeating itself yet"),
+    }
+}
+
+/// Run the game without showing it until it settles down, giving up after `max_generations`, and
+/// report how it ended up. How it's going is printed every `progress_every` generations, since
+/// some patterns take a long time to settle.
+fn run_until_stable(
+    mut game: conway::WithStats<Box<dyn conway::GameOfLife>>,
+    max_generations: u64,
+    progress_every: u64,
+) {
+    let mut detector = conway::StabilityDetector::new();
+    let start = std::time::Instant::now();
+    for ticks in 0..=max_generations {
+        if ticks > 0 {
+            game.tick();
+            if progress_every > 0 && ticks % progress_every == 0 {
+                println!("Still going at {} ({:?})", game.stats(), start.elapsed());
+            }
+        }
+        let generation = game.stats().generation;
+        if let Some(stabilisation) = detector.observe(&game, generation) {
+            println!(
+                "Settled down at generation {} (noticed at generation {}, after {:?})",
+                stabilisation.periodicity.start,
+                generation,
+                start.elapsed()
+            );
+            println!("Final population: {}", stabilisation.population);
+            println!("Escaping gliders: {}", stabilisation.escaping_gliders);
+            println!("The board is {}", stabilisation.periodicity);
+            return;
+        }
+    }
+    println!(
+        "Gave up after {} generations ({:?}) without the board settling down",
+        max_generations,
+        start.elapsed()
+    );
+    println!("Finished at {}", game.stats());
+}
+
+/// Work out the rule to run the given implementation with, from the rule given on the command line
+/// or in the pattern file (or the implementation's default rule, if neither gave one). If the rule
+/// is malformed or the implementation can't run it, report that and bail out.
+fn parse_rule<R>(
+    implementation: Implementation,
+    rule: Option<&str>,
+    supported: impl Fn(&R) -> bool,
+) -> R
+where
+    R: std::str::FromStr + Default + std::fmt::Display,
+    R::Err: std::fmt::Display,
+{
+    let rule = match rule.map(str::parse).transpose() {
+        Ok(rule) => rule.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Can't run the rule: {} (try passing --rule)", e);
+            std::process::exit(1);
+        }
+    };
+    if !supported(&rule) {
+        eprintln!(
+            "The {} implementation can't run the {} rule; use generations for rules with dying \
+             states, or isotropic for rules in Hensel notation",
+            implementation, rule
+        );
+        std::process::exit(1);
+    }
+    println!("Running with rule {}", rule);
+    rule
+}
+
+/// Print out what we know about a pattern that was loaded from a file.
+fn print_pattern_details(pattern: &conway::Pattern) {
+    println!(
+        "Loaded pattern {} ({}x{} cells, rule {})",
+        pattern.name.as_deref().unwrap_or("(unnamed)"),
+        pattern.width,
+        pattern.height,
+        pattern.rule.as_deref().unwrap_or("not given")
+    );
+    if let Some(author) = &pattern.author {
+        println!("    by {}", author);
+    }
+    for comment in &pattern.comments {
+        println!("    {}", comment);
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! But you probably don't need to change it, unless you want to extend the UI with new behavior.
 
-use conway::GameOfLife;
+use conway::{GameOfLife, PeriodDetector, WithStats};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
@@ -30,6 +30,12 @@ pub struct UiOptions {
 
     /// Should the game start pause, or be running immediately as soon as the event loop starts?
     pub start_paused: bool,
+
+    /// What to show in the window's title bar, e.g. the name of the pattern being run.
+    pub title: String,
+
+    /// How many generations to jump ahead by when J is pressed.
+    pub jump_generations: u64,
 }
 
 impl UiOptions {
@@ -40,7 +46,12 @@ impl UiOptions {
     }
 }
 
-pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
+/// Show the game in a window and run it. It's wrapped up in `WithStats` to keep track of which
+/// generation it's on and how it's going, to show in the title bar.
+pub fn run_game(mut game: WithStats<Box<dyn GameOfLife>>, options: &UiOptions) {
+    // notice when the game settles down into repeating itself
+    let mut period_detector = PeriodDetector::new();
+    period_detector.observe(&game, game.stats().generation);
     let mut sim = Simulation::new();
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -51,7 +62,7 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
     // `surface()` method.
     let window = video_subsystem
         .window(
-            "RustLife",
+            &options.title,
             (options.square_size as usize * game.width()) as u32,
             (options.square_size as usize * game.height()) as u32,
         )
@@ -91,10 +102,21 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
         sim.toggle_state(); // start simulation
         println!("(hit Space to pause/unpause simulation; click cells to toggle them)");
     }
+    println!("(use the arrow keys to move around the board, and C to center on the live cells)");
+    println!(
+        "(hit J to jump {} generations ahead)",
+        options.jump_generations
+    );
+
+    // the window shows a viewport onto the board, which starts off with the top left of the board
+    // at the top left of the window, but can be moved around with the arrow keys (which matters
+    // most for games whose board goes on forever)
+    let (view_width, view_height) = (game.width() as i64, game.height() as i64);
+    let mut viewport = Viewport { x: 0, y: 0 };
 
     let mut event_pump = sdl_context.event_pump().unwrap();
     let mut last_tick_time = SystemTime::now();
-    let mut last_cell_toggled: Option<(usize, usize)> = None;
+    let mut last_cell_toggled: Option<(i64, i64)> = None;
     'running: loop {
         // get the inputs here
         for event in event_pump.poll_iter() {
@@ -115,40 +137,90 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
                 } => {
                     sim.toggle_state();
                 }
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            keycode
+                            @ (Keycode::Left | Keycode::Right | Keycode::Up | Keycode::Down),
+                        ),
+                    ..
+                } => {
+                    // move a quarter of the window at a time
+                    let (step_x, step_y) = ((view_width / 4).max(1), (view_height / 4).max(1));
+                    match keycode {
+                        Keycode::Left => viewport.x -= step_x,
+                        Keycode::Right => viewport.x += step_x,
+                        Keycode::Up => viewport.y -= step_y,
+                        _ => viewport.y += step_y,
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::C),
+                    repeat: false,
+                    ..
+                } => {
+                    // bring the live cells (or as many of them as fit) into the middle of the
+                    // window, e.g. to catch up with a spaceship
+                    if let Some(bounds) = game.live_bounding_box() {
+                        viewport.x = bounds.x + bounds.width as i64 / 2 - view_width / 2;
+                        viewport.y = bounds.y + bounds.height as i64 / 2 - view_height / 2;
+                        println!("Centered view on the live cells at {:?}", bounds);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::J),
+                    repeat: false,
+                    ..
+                } => {
+                    // skip straight past a load of generations, which for some implementations
+                    // is far quicker than ticking through them one by one
+                    let start = SystemTime::now();
+                    game.tick_n(options.jump_generations);
+                    println!(
+                        "Jumped {} generations in {:?}",
+                        options.jump_generations,
+                        start.elapsed().unwrap_or_default()
+                    );
+                    observe_period(&mut period_detector, &game);
+                    canvas
+                        .window_mut()
+                        .set_title(&window_title(options, &game, &period_detector))
+                        .unwrap();
+                }
                 Event::MouseButtonDown {
                     x,
                     y,
                     mouse_btn: MouseButton::Left,
                     ..
                 } => {
-                    let cell_x = (x / options.square_size as i32) as usize;
-                    let cell_y = (y / options.square_size as i32) as usize;
+                    let (cell_x, cell_y) = viewport.cell_at(x, y, options.square_size);
                     println!(
                         "Attempting to toggle cell at {}, {} due to mouse click at {}, {}",
                         cell_x, cell_y, x, y
                     );
-                    game.toggle_cell(cell_x, cell_y);
+                    game.toggle_cell_at(cell_x, cell_y);
+                    // the board's been changed by hand, so whatever it was repeating is gone
+                    period_detector.reset();
                     last_cell_toggled = Some((cell_x, cell_y));
                 }
                 Event::MouseMotion {
                     x, y, mousestate, ..
-                } => {
-                    if mousestate.is_mouse_button_pressed(MouseButton::Left) {
-                        let cell_x = (x / options.square_size as i32) as usize;
-                        let cell_y = (y / options.square_size as i32) as usize;
+                } if mousestate.is_mouse_button_pressed(MouseButton::Left) => {
+                    let (cell_x, cell_y) = viewport.cell_at(x, y, options.square_size);
 
-                        // When toggling cells via mouse move, it's easy to revert a toggle by moving
-                        // the mouse slowly within the same cell, which is not ever what you want.
-                        // So we track the last cell that was toggled and don't allow it to be
-                        // toggled via mouse move again.
-                        if Some((cell_x, cell_y)) != last_cell_toggled {
-                            println!(
-                                "Attempting to toggle cell at {}, {} due to mouse motion at {}, {}",
-                                cell_x, cell_y, x, y
-                            );
-                            game.toggle_cell(cell_x, cell_y);
-                            last_cell_toggled = Some((cell_x, cell_y));
-                        }
+                    // When toggling cells via mouse move, it's easy to revert a toggle by moving
+                    // the mouse slowly within the same cell, which is not ever what you want.
+                    // So we track the last cell that was toggled and don't allow it to be
+                    // toggled via mouse move again.
+                    if Some((cell_x, cell_y)) != last_cell_toggled {
+                        println!(
+                            "Attempting to toggle cell at {}, {} due to mouse motion at {}, {}",
+                            cell_x, cell_y, x, y
+                        );
+                        game.toggle_cell_at(cell_x, cell_y);
+                        // the board's been changed by hand, so whatever it was repeating is gone
+                        period_detector.reset();
+                        last_cell_toggled = Some((cell_x, cell_y));
                     }
                 }
                 _ => {}
@@ -161,6 +233,11 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
                 Ok(duration) if options.ready_for_next_tick(duration) => {
                     game.tick();
                     last_tick_time = SystemTime::now();
+                    observe_period(&mut period_detector, &game);
+                    canvas
+                        .window_mut()
+                        .set_title(&window_title(options, &game, &period_detector))
+                        .unwrap();
                 }
                 _ => {
                     // clock drift or not enough time has elapsed since last tick - do nothing yet
@@ -176,28 +253,31 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
         } else {
             &paused_texture
         };
-        // there are more efficient ways to iterate over all cells, but the API used here is easiest
-        // to implement for people with little to no Rust experience, so we'll stick with this.
-        for x in 0..game.width() {
-            for y in 0..game.height() {
-                match game.is_cell_alive(x, y) {
-                    Some(true) => canvas
-                        .copy(
-                            square_texture,
-                            None,
-                            Rect::new(
-                                (x * options.square_size as usize) as i32,
-                                (y * options.square_size as usize) as i32,
-                                options.square_size as u32,
-                                options.square_size as u32,
-                            ),
-                        )
-                        .unwrap(),
-                    Some(false) => (), // do nothing, empty canvas block is sufficient for a dead cell
-                    None => panic!(
-                        "logic error in checking cell liveness! x={}, y={} and got no result",
-                        x, y
-                    ),
+        let cell_rect = |x: i64, y: i64| {
+            Rect::new(
+                (x * options.square_size as i64) as i32,
+                (y * options.square_size as i64) as i32,
+                options.square_size as u32,
+                options.square_size as u32,
+            )
+        };
+        // ask about each cell in the view, rather than going through every live cell on the board,
+        // since boards that go on forever can have far more live cells than fit in the window
+        for x in 0..view_width {
+            for y in 0..view_height {
+                match game.cell_state_at(viewport.x + x, viewport.y + y) {
+                    Some(0) => (), // do nothing, empty canvas block is sufficient for a dead cell
+                    Some(1) => canvas.copy(square_texture, None, cell_rect(x, y)).unwrap(),
+                    Some(dying_state) => {
+                        canvas.set_draw_color(dying_color(dying_state, game.num_states()));
+                        canvas.fill_rect(cell_rect(x, y)).unwrap();
+                    }
+                    // the viewport has been moved past the edge of the board, so grey out the
+                    // cells that aren't on it
+                    None => {
+                        canvas.set_draw_color(Color::RGB(32, 32, 32));
+                        canvas.fill_rect(cell_rect(x, y)).unwrap();
+                    }
                 }
             }
         }
@@ -206,6 +286,67 @@ pub fn run_game(mut game: Box<dyn GameOfLife>, options: &UiOptions) {
     }
 }
 
+/// Look at the board for the period detector, and say so if that shows the board has just settled
+/// down into repeating itself.
+fn observe_period(period_detector: &mut PeriodDetector, game: &WithStats<Box<dyn GameOfLife>>) {
+    let already_periodic = period_detector.periodicity().is_some();
+    if let Some(periodicity) = period_detector.observe(game, game.stats().generation) {
+        if !already_periodic {
+            println!("Board is now {}", periodicity);
+        }
+    }
+}
+
+/// Work out what to show in the window's title bar: the title from the options, followed by the
+/// game's stats, how the board repeats (once it does), and anything the implementation has to say
+/// about how the last tick went (like how much of the board it had to look at).
+fn window_title(
+    options: &UiOptions,
+    game: &WithStats<Box<dyn GameOfLife>>,
+    period_detector: &PeriodDetector,
+) -> String {
+    let mut title = format!("{} - {}", options.title, game.stats());
+    if let Some(periodicity) = period_detector.periodicity() {
+        title += &format!(" - {}", periodicity);
+    }
+    if let Some(report) = game.tick_report() {
+        title += &format!(" - {}", report);
+    }
+    title
+}
+
+/// Pick the colour for a cell that's dying (i.e. in state 2 or up, for games with Generations
+/// rules): cells that have only just started dying are bright red, fading towards dark blue as
+/// they get closer to being dead.
+fn dying_color(state: u8, num_states: u8) -> Color {
+    // how far through dying the cell is, from 0.0 (just started) to 1.0 (about to be dead)
+    let dying_states = num_states.saturating_sub(2).max(1);
+    let progress = (state - 2) as f32 / dying_states as f32;
+    Color::RGB(
+        (220.0 * (1.0 - progress)) as u8 + 20,
+        40,
+        (120.0 * progress) as u8 + 40,
+    )
+}
+
+/// Which part of the board the window is showing: the coordinates of the cell at the top left of
+/// the window.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Viewport {
+    x: i64,
+    y: i64,
+}
+
+impl Viewport {
+    /// Work out which cell is at the given pixel of the window.
+    fn cell_at(&self, pixel_x: i32, pixel_y: i32, square_size: u8) -> (i64, i64) {
+        (
+            self.x + (pixel_x / square_size as i32) as i64,
+            self.y + (pixel_y / square_size as i32) as i64,
+        )
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum SimulationState {
     Paused,
@@ -249,7 +390,7 @@ fn generate_textures<'a>(
         .unwrap();
     // let's change the textures we just created
     {
-        let textures = vec![
+        let textures = [
             (&mut square_texture1, TextureColor::Yellow),
             (&mut square_texture2, TextureColor::White),
         ];